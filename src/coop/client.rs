@@ -0,0 +1,187 @@
+//coop.client.rs
+use super::protocol::{Seen, ToClient, ToHost};
+use crate::lan;
+use minesweeper::engine::GameStatus;
+use std::io::{self, Write};
+use std::net::TcpStream;
+use std::sync::mpsc::{Receiver, TryRecvError};
+
+/// Someone on the shared board, including this client.
+#[derive(Debug, Clone)]
+pub struct Partner {
+    pub id: u32,
+    pub name: String,
+    pub cursor: Option<(usize, usize)>,
+}
+
+/// A connection to a co-op host and the board as it last described it.
+/// Moves are only sent; the board changes when the host answers.
+pub struct CoopClient {
+    stream: TcpStream,
+    incoming: Receiver<ToClient>,
+    pub id: Option<u32>,
+    pub partners: Vec<Partner>,
+    pub width: usize,
+    pub height: usize,
+    pub mines: usize,
+    cells: Vec<Seen>,
+    pub status: GameStatus,
+    /// Who won or lost the current board.
+    pub decided_by: Option<u32>,
+    /// The host's answer to a refused move.
+    pub notice: Option<String>,
+    /// The connection dropped.
+    pub error: Option<String>,
+}
+
+impl CoopClient {
+    pub fn join(address: &str, name: &str) -> io::Result<Self> {
+        let (stream, incoming) = lan::connect(address, ToClient::parse)?;
+        let mut client = Self {
+            stream,
+            incoming,
+            id: None,
+            partners: Vec::new(),
+            width: 0,
+            height: 0,
+            mines: 0,
+            cells: Vec::new(),
+            status: GameStatus::Ready,
+            decided_by: None,
+            notice: None,
+            error: None,
+        };
+        client.send(&ToHost::Hello {
+            name: name.to_string(),
+        });
+        Ok(client)
+    }
+
+    /// Applies everything the host sent since the last call. Returns
+    /// whether anything changed.
+    pub fn poll(&mut self) -> bool {
+        let mut changed = false;
+        loop {
+            let message = match self.incoming.try_recv() {
+                Ok(message) => message,
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    if self.error.is_none() {
+                        self.error = Some("lost the connection to the host".to_string());
+                        changed = true;
+                    }
+                    break;
+                }
+            };
+            changed = true;
+            match message {
+                ToClient::Welcome { id } => self.id = Some(id),
+                ToClient::Joined { id, name } => {
+                    if !self.partners.iter().any(|p| p.id == id) {
+                        self.partners.push(Partner {
+                            id,
+                            name,
+                            cursor: None,
+                        });
+                    }
+                }
+                ToClient::Left { id } => self.partners.retain(|p| p.id != id),
+                ToClient::Board {
+                    width,
+                    height,
+                    mines,
+                } => {
+                    (self.width, self.height, self.mines) = (width, height, mines);
+                    self.cells = vec![Seen::Hidden; width * height];
+                    self.decided_by = None;
+                    self.notice = None;
+                    for partner in &mut self.partners {
+                        partner.cursor = None;
+                    }
+                }
+                ToClient::Cells(cells) => {
+                    for (x, y, seen) in cells {
+                        if x < self.width && y < self.height {
+                            self.cells[y * self.width + x] = seen;
+                        }
+                    }
+                }
+                ToClient::Status { status, by } => {
+                    self.status = status;
+                    if by.is_some() {
+                        self.decided_by = by;
+                    }
+                }
+                ToClient::Cursor { id, x, y } => {
+                    if let Some(partner) = self.partners.iter_mut().find(|p| p.id == id) {
+                        partner.cursor = Some((x, y));
+                    }
+                }
+                ToClient::Error { message } => self.notice = Some(message),
+            }
+        }
+        changed
+    }
+
+    pub fn seen(&self, x: usize, y: usize) -> Seen {
+        self.cells[y * self.width + x]
+    }
+
+    pub fn flags(&self) -> usize {
+        self.cells.iter().filter(|&&s| s == Seen::Flagged).count()
+    }
+
+    pub fn reveal(&mut self, x: usize, y: usize) {
+        self.send(&ToHost::Reveal { x, y });
+    }
+
+    pub fn chord(&mut self, x: usize, y: usize) {
+        self.send(&ToHost::Chord { x, y });
+    }
+
+    pub fn mark(&mut self, x: usize, y: usize) {
+        self.send(&ToHost::Mark { x, y });
+    }
+
+    pub fn point_at(&mut self, x: usize, y: usize) {
+        self.send(&ToHost::Cursor { x, y });
+    }
+
+    pub fn new_game(&mut self) {
+        self.send(&ToHost::NewGame);
+    }
+
+    fn send(&mut self, message: &ToHost) {
+        self.notice = None;
+        if let Err(e) = writeln!(self.stream, "{}", message.encode()) {
+            self.error = Some(format!("lost the connection to the host: {}", e));
+        }
+    }
+
+    /// One line on how the shared game stands.
+    pub fn status_line(&self) -> String {
+        if let Some(error) = &self.error {
+            return format!("Co-op: {}", error);
+        }
+        let by = match self.decided_by {
+            Some(id) if Some(id) == self.id => "you".to_string(),
+            Some(id) => self
+                .partners
+                .iter()
+                .find(|p| p.id == id)
+                .map_or("someone".to_string(), |p| p.name.clone()),
+            None => "someone".to_string(),
+        };
+        match self.status {
+            _ if self.width == 0 => "Waiting for the host.".to_string(),
+            GameStatus::Won => format!("Board cleared, last cell opened by {}!", by),
+            GameStatus::Lost => format!("Game over, {} hit a mine.", by),
+            GameStatus::Ready | GameStatus::Playing => format!(
+                "Flags: {}/{}  Players: {}",
+                self.flags(),
+                self.mines,
+                self.partners.len()
+            ),
+        }
+    }
+}
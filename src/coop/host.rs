@@ -0,0 +1,315 @@
+//coop.host.rs
+//! The co-op server. It owns the only real `Board`: players send moves,
+//! the host plays them through the engine and sends everyone the cells
+//! that changed. Players may join at any time and the host keeps running,
+//! starting a new board whenever someone asks for one after a game ends.
+
+use super::protocol::{Seen, ToClient, ToHost};
+use crate::config::Config;
+use crate::lan::{self, Event};
+use minesweeper::engine::{Board, Difficulty, GameError, GameStatus};
+use std::io;
+use std::net::TcpStream;
+
+struct Player {
+    id: u32,
+    stream: TcpStream,
+    /// Set by `hello`; players without one see nothing yet.
+    name: Option<String>,
+    cursor: Option<(usize, usize)>,
+}
+
+struct Host {
+    difficulty: Difficulty,
+    question_marks: bool,
+    board: Board,
+    players: Vec<Player>,
+}
+
+/// Hosts co-op games on `config.race_port` until interrupted.
+pub fn run(config: &Config) -> io::Result<()> {
+    let inbox = lan::listen(config.race_port, ToHost::parse)?;
+    println!(
+        "Hosting a co-op game on port {}: {}.",
+        config.race_port, config.difficulty
+    );
+    let mut host = Host {
+        difficulty: config.difficulty,
+        question_marks: config.question_marks,
+        board: Board::with_difficulty(config.difficulty),
+        players: Vec::new(),
+    };
+    host.new_board();
+    for event in inbox {
+        host.handle(event);
+    }
+    Ok(())
+}
+
+impl Host {
+    fn handle(&mut self, event: Event<ToHost>) {
+        match event {
+            Event::Connected(id, stream) => self.players.push(Player {
+                id,
+                stream,
+                name: None,
+                cursor: None,
+            }),
+            Event::Message(id, ToHost::Hello { name }) => self.hello(id, name),
+            Event::Message(id, message) => {
+                if self.name_of(id).is_none() {
+                    return;
+                }
+                match message {
+                    ToHost::Hello { .. } => {}
+                    ToHost::Reveal { x, y } => self.play(id, x, y, Board::reveal),
+                    ToHost::Chord { x, y } => self.play(id, x, y, Board::chord),
+                    ToHost::Mark { x, y } => self.play(id, x, y, Board::toggle_state),
+                    ToHost::Cursor { x, y } => {
+                        if !self.board.contains(x, y) {
+                            return;
+                        }
+                        if let Some(player) = self.players.iter_mut().find(|p| p.id == id) {
+                            player.cursor = Some((x, y));
+                        }
+                        self.broadcast(&ToClient::Cursor { id, x, y });
+                    }
+                    ToHost::NewGame => {
+                        if self.board.is_finished() {
+                            println!("{} started a new board.", self.name_of(id).unwrap_or(""));
+                            self.new_board();
+                            self.broadcast_board();
+                        } else {
+                            let message = "finish this board first".to_string();
+                            self.send(id, &ToClient::Error { message });
+                        }
+                    }
+                }
+            }
+            Event::Disconnected(id) => {
+                let Some(i) = self.players.iter().position(|p| p.id == id) else {
+                    return;
+                };
+                if let Some(name) = self.players.remove(i).name {
+                    println!("{} left.", name);
+                    self.broadcast(&ToClient::Left { id });
+                }
+            }
+        }
+    }
+
+    fn hello(&mut self, id: u32, name: String) {
+        let Some(player) = self.players.iter_mut().find(|p| p.id == id) else {
+            return;
+        };
+        if player.name.is_some() {
+            return;
+        }
+        player.name = Some(name.clone());
+        let mut catch_up = vec![ToClient::Welcome { id }];
+        catch_up.extend(self.board_messages());
+        for other in self.players.iter().filter(|p| p.id != id) {
+            let Some(other_name) = &other.name else {
+                continue;
+            };
+            catch_up.push(ToClient::Joined {
+                id: other.id,
+                name: other_name.clone(),
+            });
+            if let Some((x, y)) = other.cursor {
+                catch_up.push(ToClient::Cursor { id: other.id, x, y });
+            }
+        }
+        for message in &catch_up {
+            self.send(id, message);
+        }
+        self.broadcast(&ToClient::Joined {
+            id,
+            name: name.clone(),
+        });
+        println!("{} joined.", name);
+    }
+
+    /// Plays one player's move and shares what it changed. Moves the engine
+    /// refuses go back to that player only.
+    fn play(
+        &mut self,
+        id: u32,
+        x: usize,
+        y: usize,
+        play: fn(&mut Board, usize, usize) -> Result<(), GameError>,
+    ) {
+        let before = self.seen();
+        let status = self.board.status();
+        match play(&mut self.board, x, y) {
+            Ok(()) | Err(GameError::HitMine { .. }) => {}
+            Err(e) => {
+                let message = e.to_string();
+                self.send(id, &ToClient::Error { message });
+                return;
+            }
+        }
+        let changed: Vec<_> = self
+            .seen()
+            .into_iter()
+            .zip(before)
+            .enumerate()
+            .filter(|(_, (after, before))| after != before)
+            .map(|(i, (after, _))| (i % self.board.width(), i / self.board.width(), after))
+            .collect();
+        if !changed.is_empty() {
+            self.broadcast(&ToClient::Cells(changed));
+        }
+        if self.board.status() != status {
+            self.broadcast(&ToClient::Status {
+                status: self.board.status(),
+                by: Some(id),
+            });
+            let name = self.name_of(id).unwrap_or("");
+            match self.board.status() {
+                GameStatus::Won => println!("{} cleared the board.", name),
+                GameStatus::Lost => println!("{} hit a mine.", name),
+                GameStatus::Ready | GameStatus::Playing => {}
+            }
+        }
+    }
+
+    fn seen(&self) -> Vec<Seen> {
+        (0..self.board.height())
+            .flat_map(|y| (0..self.board.width()).map(move |x| (x, y)))
+            .map(|(x, y)| Seen::of(&self.board, x, y))
+            .collect()
+    }
+
+    fn new_board(&mut self) {
+        self.board = Board::with_difficulty(self.difficulty);
+        self.board.set_question_marks(self.question_marks);
+    }
+
+    /// Everything a player needs to draw the current board.
+    fn board_messages(&self) -> Vec<ToClient> {
+        let opened: Vec<_> = self
+            .seen()
+            .into_iter()
+            .enumerate()
+            .filter(|(_, seen)| *seen != Seen::Hidden)
+            .map(|(i, seen)| (i % self.board.width(), i / self.board.width(), seen))
+            .collect();
+        let mut messages = vec![ToClient::Board {
+            width: self.board.width(),
+            height: self.board.height(),
+            mines: self.board.mine_count(),
+        }];
+        if !opened.is_empty() {
+            messages.push(ToClient::Cells(opened));
+        }
+        messages.push(ToClient::Status {
+            status: self.board.status(),
+            by: None,
+        });
+        messages
+    }
+
+    fn broadcast_board(&mut self) {
+        for message in self.board_messages() {
+            self.broadcast(&message);
+        }
+    }
+
+    fn name_of(&self, id: u32) -> Option<&str> {
+        self.players
+            .iter()
+            .find(|p| p.id == id)
+            .and_then(|p| p.name.as_deref())
+    }
+
+    fn send(&mut self, id: u32, message: &ToClient) {
+        if let Some(player) = self.players.iter_mut().find(|p| p.id == id) {
+            lan::send_line(&mut player.stream, &message.encode());
+        }
+    }
+
+    fn broadcast(&mut self, message: &ToClient) {
+        let line = message.encode();
+        for player in self.players.iter_mut().filter(|p| p.name.is_some()) {
+            lan::send_line(&mut player.stream, &line);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader};
+    use std::net::TcpListener;
+
+    /// Connects player `id` to `host` over localhost and says hello.
+    fn join(host: &mut Host, id: u32, name: &str) -> BufReader<TcpStream> {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (server, _) = listener.accept().unwrap();
+        host.handle(Event::Connected(id, server));
+        let name = name.to_string();
+        host.handle(Event::Message(id, ToHost::Hello { name }));
+        BufReader::new(client)
+    }
+
+    fn receive(client: &mut BufReader<TcpStream>) -> ToClient {
+        let mut line = String::new();
+        client.read_line(&mut line).unwrap();
+        ToClient::parse(line.trim_end()).unwrap()
+    }
+
+    /// Reads past everything up to `id` joining.
+    fn skip_to_join(client: &mut BufReader<TcpStream>, id: u32) {
+        while !matches!(receive(client), ToClient::Joined { id: joined, .. } if joined == id) {}
+    }
+
+    #[test]
+    fn moves_share_only_the_changed_cells() {
+        // `*1000`: one mine in the corner of a single row.
+        let mut host = Host {
+            difficulty: Difficulty::Easy,
+            question_marks: false,
+            board: Board::from_layout(5, 1, &[(0, 0)], 1),
+            players: Vec::new(),
+        };
+        let mut ada = join(&mut host, 1, "Ada");
+        skip_to_join(&mut ada, 1);
+        let mut bo = join(&mut host, 2, "Bo");
+        skip_to_join(&mut ada, 2);
+        skip_to_join(&mut bo, 2);
+
+        // The board is already in play, so only the cells change until the
+        // last move wins and uncovers the mine.
+        host.handle(Event::Message(2, ToHost::Reveal { x: 1, y: 0 }));
+        host.handle(Event::Message(1, ToHost::Mark { x: 0, y: 0 }));
+        host.handle(Event::Message(1, ToHost::Chord { x: 1, y: 0 }));
+        for client in [&mut ada, &mut bo] {
+            assert_eq!(
+                receive(client),
+                ToClient::Cells(vec![(1, 0, Seen::Open(1))])
+            );
+            assert_eq!(
+                receive(client),
+                ToClient::Cells(vec![(0, 0, Seen::Flagged)])
+            );
+            assert_eq!(
+                receive(client),
+                ToClient::Cells(vec![
+                    (0, 0, Seen::Mine),
+                    (2, 0, Seen::Open(0)),
+                    (3, 0, Seen::Open(0)),
+                    (4, 0, Seen::Open(0)),
+                ])
+            );
+            assert_eq!(
+                receive(client),
+                ToClient::Status {
+                    status: GameStatus::Won,
+                    by: Some(1),
+                }
+            );
+        }
+    }
+}
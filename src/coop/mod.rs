@@ -0,0 +1,12 @@
+//coop.mod.rs
+//! Co-operative play on a local network. A host started with
+//! `minesweeper coop` owns one board that every player works on together;
+//! each player's moves go through it and everyone sees the same cells and
+//! each other's cursors.
+
+mod client;
+mod host;
+mod protocol;
+
+pub use client::CoopClient;
+pub use host::run as host;
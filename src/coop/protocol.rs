@@ -0,0 +1,323 @@
+//coop.protocol.rs
+//! Messages between co-op players and the host, one per line of text.
+//!
+//! Clients send `hello NAME`, `reveal X Y`, `chord X Y`, `mark X Y`,
+//! `cursor X Y` and `new`. The host answers with `welcome ID`,
+//! `joined ID NAME`, `left ID`, `board W H MINES`, `cells X Y S ...`,
+//! `status ready|playing|won|lost [ID]`, `cursor ID X Y` and `error TEXT`.
+//! In `cells`, every cell is a position and what it shows: `.` hidden,
+//! `F` flagged, `?` questioned, `0`-`8` open and `*` an open mine.
+
+use crate::lan::{number, one_line, rest};
+use minesweeper::engine::{Board, Cell, CellState, GameStatus};
+
+/// A cell as the players see it; the host never sends more than this.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Seen {
+    Hidden,
+    Flagged,
+    Questioned,
+    Open(u8),
+    Mine,
+}
+
+impl Seen {
+    pub fn of(board: &Board, x: usize, y: usize) -> Self {
+        match (board.cell_state(x, y), board.cell(x, y)) {
+            (CellState::Hidden, _) => Seen::Hidden,
+            (CellState::Flagged, _) => Seen::Flagged,
+            (CellState::Questioned, _) => Seen::Questioned,
+            (CellState::Revealed, Cell::Mine) => Seen::Mine,
+            (CellState::Revealed, Cell::Empty) => Seen::Open(0),
+            (CellState::Revealed, Cell::Number(n)) => Seen::Open(*n),
+        }
+    }
+
+    /// The state and content to draw; hidden contents read as empty.
+    pub fn parts(self) -> (CellState, Cell) {
+        match self {
+            Seen::Hidden => (CellState::Hidden, Cell::Empty),
+            Seen::Flagged => (CellState::Flagged, Cell::Empty),
+            Seen::Questioned => (CellState::Questioned, Cell::Empty),
+            Seen::Open(0) => (CellState::Revealed, Cell::Empty),
+            Seen::Open(n) => (CellState::Revealed, Cell::Number(n)),
+            Seen::Mine => (CellState::Revealed, Cell::Mine),
+        }
+    }
+
+    fn symbol(self) -> char {
+        match self {
+            Seen::Hidden => '.',
+            Seen::Flagged => 'F',
+            Seen::Questioned => '?',
+            Seen::Open(n) => (b'0' + n) as char,
+            Seen::Mine => '*',
+        }
+    }
+
+    fn parse(word: &str) -> Result<Self, String> {
+        match word {
+            "." => Ok(Seen::Hidden),
+            "F" => Ok(Seen::Flagged),
+            "?" => Ok(Seen::Questioned),
+            "*" => Ok(Seen::Mine),
+            _ => match word.parse() {
+                Ok(n) if n <= 8 => Ok(Seen::Open(n)),
+                _ => Err(format!("bad cell {:?}", word)),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ToHost {
+    Hello { name: String },
+    Reveal { x: usize, y: usize },
+    Chord { x: usize, y: usize },
+    Mark { x: usize, y: usize },
+    Cursor { x: usize, y: usize },
+    NewGame,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ToClient {
+    Welcome {
+        id: u32,
+    },
+    Joined {
+        id: u32,
+        name: String,
+    },
+    Left {
+        id: u32,
+    },
+    /// A fresh board; every cell is hidden until `cells` says otherwise.
+    Board {
+        width: usize,
+        height: usize,
+        mines: usize,
+    },
+    Cells(Vec<(usize, usize, Seen)>),
+    /// `by` is the player whose move changed it, if any.
+    Status {
+        status: GameStatus,
+        by: Option<u32>,
+    },
+    Cursor {
+        id: u32,
+        x: usize,
+        y: usize,
+    },
+    Error {
+        message: String,
+    },
+}
+
+impl ToHost {
+    pub fn encode(&self) -> String {
+        match self {
+            ToHost::Hello { name } => format!("hello {}", one_line(name)),
+            ToHost::Reveal { x, y } => format!("reveal {} {}", x, y),
+            ToHost::Chord { x, y } => format!("chord {} {}", x, y),
+            ToHost::Mark { x, y } => format!("mark {} {}", x, y),
+            ToHost::Cursor { x, y } => format!("cursor {} {}", x, y),
+            ToHost::NewGame => "new".to_string(),
+        }
+    }
+
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words.as_slice() {
+            ["hello", ..] => Ok(ToHost::Hello {
+                name: rest(line, "hello"),
+            }),
+            [verb, x, y] => {
+                let (x, y) = (number(x)?, number(y)?);
+                match *verb {
+                    "reveal" => Ok(ToHost::Reveal { x, y }),
+                    "chord" => Ok(ToHost::Chord { x, y }),
+                    "mark" => Ok(ToHost::Mark { x, y }),
+                    "cursor" => Ok(ToHost::Cursor { x, y }),
+                    _ => Err(format!("unexpected message {:?}", line)),
+                }
+            }
+            ["new"] => Ok(ToHost::NewGame),
+            _ => Err(format!("unexpected message {:?}", line)),
+        }
+    }
+}
+
+impl ToClient {
+    pub fn encode(&self) -> String {
+        match self {
+            ToClient::Welcome { id } => format!("welcome {}", id),
+            ToClient::Joined { id, name } => format!("joined {} {}", id, one_line(name)),
+            ToClient::Left { id } => format!("left {}", id),
+            ToClient::Board {
+                width,
+                height,
+                mines,
+            } => format!("board {} {} {}", width, height, mines),
+            ToClient::Cells(cells) => cells
+                .iter()
+                .fold("cells".to_string(), |line, (x, y, seen)| {
+                    format!("{} {} {} {}", line, x, y, seen.symbol())
+                }),
+            ToClient::Status { status, by } => {
+                let status = match status {
+                    GameStatus::Ready => "ready",
+                    GameStatus::Playing => "playing",
+                    GameStatus::Won => "won",
+                    GameStatus::Lost => "lost",
+                };
+                match by {
+                    Some(id) => format!("status {} {}", status, id),
+                    None => format!("status {}", status),
+                }
+            }
+            ToClient::Cursor { id, x, y } => format!("cursor {} {} {}", id, x, y),
+            ToClient::Error { message } => format!("error {}", one_line(message)),
+        }
+    }
+
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words.as_slice() {
+            ["welcome", id] => Ok(ToClient::Welcome { id: number(id)? }),
+            ["joined", id, ..] => Ok(ToClient::Joined {
+                id: number(id)?,
+                name: rest(&rest(line, "joined"), id),
+            }),
+            ["left", id] => Ok(ToClient::Left { id: number(id)? }),
+            ["board", width, height, mines] => Ok(ToClient::Board {
+                width: number(width)?,
+                height: number(height)?,
+                mines: number(mines)?,
+            }),
+            ["cells", cells @ ..] if cells.len() % 3 == 0 => cells
+                .chunks(3)
+                .map(|cell| Ok((number(cell[0])?, number(cell[1])?, Seen::parse(cell[2])?)))
+                .collect::<Result<_, String>>()
+                .map(ToClient::Cells),
+            ["status", status, by @ ..] if by.len() <= 1 => Ok(ToClient::Status {
+                status: match *status {
+                    "ready" => GameStatus::Ready,
+                    "playing" => GameStatus::Playing,
+                    "won" => GameStatus::Won,
+                    "lost" => GameStatus::Lost,
+                    _ => return Err(format!("bad status {:?}", status)),
+                },
+                by: by.first().map(|id| number(id)).transpose()?,
+            }),
+            ["cursor", id, x, y] => Ok(ToClient::Cursor {
+                id: number(id)?,
+                x: number(x)?,
+                y: number(y)?,
+            }),
+            ["error", ..] => Ok(ToClient::Error {
+                message: rest(line, "error"),
+            }),
+            _ => Err(format!("unexpected message {:?}", line)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn host_messages_round_trip() {
+        let messages = [
+            ToHost::Hello {
+                name: "Ada Lovelace".to_string(),
+            },
+            ToHost::Reveal { x: 0, y: 1 },
+            ToHost::Chord { x: 12, y: 7 },
+            ToHost::Mark { x: 3, y: 0 },
+            ToHost::Cursor { x: 29, y: 15 },
+            ToHost::NewGame,
+        ];
+        for message in messages {
+            assert_eq!(ToHost::parse(&message.encode()), Ok(message));
+        }
+    }
+
+    #[test]
+    fn client_messages_round_trip() {
+        let cells = vec![
+            (0, 0, Seen::Hidden),
+            (1, 0, Seen::Flagged),
+            (2, 0, Seen::Questioned),
+            (3, 0, Seen::Open(0)),
+            (4, 0, Seen::Open(8)),
+            (5, 12, Seen::Mine),
+        ];
+        let mut messages = vec![
+            ToClient::Welcome { id: 1 },
+            ToClient::Joined {
+                id: 2,
+                name: "Bo 2".to_string(),
+            },
+            ToClient::Left { id: 2 },
+            ToClient::Board {
+                width: 30,
+                height: 16,
+                mines: 99,
+            },
+            ToClient::Cells(cells),
+            ToClient::Cursor { id: 3, x: 4, y: 5 },
+            ToClient::Error {
+                message: "finish this board first".to_string(),
+            },
+        ];
+        for status in [
+            GameStatus::Ready,
+            GameStatus::Playing,
+            GameStatus::Won,
+            GameStatus::Lost,
+        ] {
+            messages.push(ToClient::Status { status, by: None });
+            messages.push(ToClient::Status {
+                status,
+                by: Some(7),
+            });
+        }
+        for message in messages {
+            assert_eq!(ToClient::parse(&message.encode()), Ok(message));
+        }
+        assert_eq!(
+            ToClient::Cells(vec![(3, 4, Seen::Open(2)), (0, 1, Seen::Flagged)]).encode(),
+            "cells 3 4 2 0 1 F"
+        );
+    }
+
+    #[test]
+    fn malformed_lines_are_rejected() {
+        for line in [
+            "",
+            "reveal 1",
+            "reveal x 2",
+            "mark 1 2 3",
+            "jump 1 2",
+            "new game",
+        ] {
+            assert!(ToHost::parse(line).is_err(), "{:?}", line);
+        }
+        for line in [
+            "",
+            "welcome",
+            "welcome one",
+            "board 9 9",
+            "cells 1 2",
+            "cells 1 2 9",
+            "cells 1 2 #",
+            "status over",
+            "status won 1 2",
+            "cursor 1 2",
+            "hello",
+        ] {
+            assert!(ToClient::parse(line).is_err(), "{:?}", line);
+        }
+    }
+}
@@ -0,0 +1,41 @@
+//daily.mod.rs
+//! Which daily challenges have been started on this machine. Only the first
+//! attempt at a day is ranked; the list lives in `daily.txt` in the data
+//! directory, one `YYYY-MM-DD` per line.
+
+use crate::paths;
+use minesweeper::engine::Day;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+fn path() -> io::Result<PathBuf> {
+    paths::data_dir()
+        .map(|dir| dir.join("daily.txt"))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no data directory"))
+}
+
+pub fn attempted(day: Day) -> io::Result<bool> {
+    let text = match fs::read_to_string(path()?) {
+        Ok(text) => text,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(false),
+        Err(e) => return Err(e),
+    };
+    let day = day.to_string();
+    Ok(text.lines().any(|line| line.trim() == day))
+}
+
+/// Records an attempt at `day` and returns whether it is the first, and so
+/// ranked.
+pub fn claim(day: Day) -> io::Result<bool> {
+    if attempted(day)? {
+        return Ok(false);
+    }
+    let path = path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", day)?;
+    Ok(true)
+}
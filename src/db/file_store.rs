@@ -0,0 +1,254 @@
+//db.file_store.rs
+use super::{GameRecord, HighScore, Ranking, ScoreEntry, ScoreStore, StoreError, CLIENT_VERSION};
+use crate::paths;
+use minesweeper::engine::Clicks;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+
+/// First line of the current file layout. Files written before headers
+/// existed have only the first four columns.
+const HEADER: &str = "# minesweeper high scores v4";
+
+/// Columns in the current layout. Columns are only ever appended, so older
+/// rows are upgraded by padding them with empty values.
+const COLUMNS: usize = 15;
+
+/// First line of the games log kept next to the scores file. Version 1
+/// logged ranked games only and had no `ranked` column.
+const GAMES_HEADER: &str = "# minesweeper games v2";
+
+/// Scores kept in a tab separated text file, one score per line:
+/// `id name time difficulty played_at width height mines seed bbbv
+/// client_version variant left_clicks right_clicks chord_clicks`.
+///
+/// Every finished game is logged to `games.tsv` in the same directory:
+/// `played_at difficulty width height mines won time seed bbbv clicks
+/// variant ranked`.
+pub struct FileStore {
+    path: PathBuf,
+}
+
+impl FileStore {
+    pub fn new(path: PathBuf) -> Result<Self, StoreError> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        // Create the file up front so permission problems show at startup.
+        OpenOptions::new().create(true).append(true).open(&path)?;
+        let store = Self { path };
+        store.migrate()?;
+        store.migrate_games()?;
+        Ok(store)
+    }
+
+    pub fn default_path() -> Option<PathBuf> {
+        paths::data_dir().map(|dir| dir.join("high_scores.tsv"))
+    }
+
+    /// Brings an older file up to the current layout by padding every row
+    /// with empty columns and adding the header.
+    fn migrate(&self) -> Result<(), StoreError> {
+        let contents = fs::read_to_string(&self.path)?;
+        if contents.lines().next() == Some(HEADER) {
+            return Ok(());
+        }
+        let mut upgraded = format!("{}\n", HEADER);
+        let rows = contents
+            .lines()
+            .filter(|line| !line.trim().is_empty() && !line.starts_with('#'));
+        for line in rows {
+            let columns = line.split('\t').count();
+            upgraded.push_str(line);
+            upgraded.push_str(&"\t".repeat(COLUMNS.saturating_sub(columns)));
+            upgraded.push('\n');
+        }
+        fs::write(&self.path, upgraded)?;
+        Ok(())
+    }
+
+    /// Upgrades a version 1 games log, whose games were all ranked, by
+    /// appending the `ranked` column to every row.
+    fn migrate_games(&self) -> Result<(), StoreError> {
+        let path = self.games_path();
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e.into()),
+        };
+        if contents.is_empty() || contents.lines().next() == Some(GAMES_HEADER) {
+            return Ok(());
+        }
+        let mut upgraded = format!("{}\n", GAMES_HEADER);
+        let rows = contents
+            .lines()
+            .filter(|line| !line.trim().is_empty() && !line.starts_with('#'));
+        for line in rows {
+            upgraded.push_str(line);
+            upgraded.push_str("\t1\n");
+        }
+        fs::write(&path, upgraded)?;
+        Ok(())
+    }
+
+    fn games_path(&self) -> PathBuf {
+        self.path.with_file_name("games.tsv")
+    }
+
+    fn load(&self) -> Result<Vec<HighScore>, StoreError> {
+        let contents = fs::read_to_string(&self.path)?;
+        contents
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty() && !line.starts_with('#'))
+            .map(|(i, line)| {
+                parse_line(line).ok_or_else(|| {
+                    StoreError::Corrupt(format!("{}:{}", self.path.display(), i + 1))
+                })
+            })
+            .collect()
+    }
+}
+
+fn parse_line(line: &str) -> Option<HighScore> {
+    let fields: Vec<&str> = line.split('\t').collect();
+    // Optional columns may be missing or empty on rows from older files.
+    let optional = |i: usize| fields.get(i).copied().filter(|v| !v.is_empty());
+    let number = |i: usize| optional(i).and_then(|v| v.parse().ok());
+    let clicks = match (number(12), number(13), number(14)) {
+        (Some(left), Some(right), Some(chord)) => Some(Clicks { left, right, chord }),
+        _ => None,
+    };
+    Some(HighScore {
+        id: fields.first()?.parse().ok()?,
+        name: fields.get(1)?.to_string(),
+        time: fields.get(2)?.parse().ok()?,
+        difficulty: fields.get(3)?.to_string(),
+        variant: optional(11).unwrap_or("classic").to_string(),
+        bbbv: number(9),
+        clicks,
+    })
+}
+
+fn parse_game(line: &str) -> Option<GameRecord> {
+    let fields: Vec<&str> = line.split('\t').collect();
+    let [played_at, difficulty, width, height, mines, won, time, seed, bbbv, clicks, variant, ranked] =
+        fields[..]
+    else {
+        return None;
+    };
+    Some(GameRecord {
+        played_at: played_at.parse().ok()?,
+        difficulty: difficulty.to_string(),
+        width: width.parse().ok()?,
+        height: height.parse().ok()?,
+        mines: mines.parse().ok()?,
+        won: won == "1",
+        time: time.parse().ok()?,
+        seed: if seed.is_empty() {
+            None
+        } else {
+            Some(seed.parse().ok()?)
+        },
+        bbbv: bbbv.parse().ok()?,
+        clicks: clicks.parse().ok()?,
+        variant: variant.to_string(),
+        ranked: ranked == "1",
+    })
+}
+
+fn sanitize(field: &str) -> String {
+    field.replace(['\t', '\n', '\r'], " ")
+}
+
+fn optional<T: ToString>(value: Option<T>) -> String {
+    value.map(|v| v.to_string()).unwrap_or_default()
+}
+
+impl ScoreStore for FileStore {
+    fn add_high_score(&self, score: &ScoreEntry) -> Result<(), StoreError> {
+        let id = self.load()?.iter().map(|s| s.id).max().unwrap_or(0) + 1;
+        let mut file = OpenOptions::new().append(true).open(&self.path)?;
+        writeln!(
+            file,
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            id,
+            sanitize(&score.name),
+            score.time,
+            sanitize(&score.difficulty),
+            score.played_at,
+            score.width,
+            score.height,
+            score.mines,
+            optional(score.seed),
+            optional(score.bbbv),
+            CLIENT_VERSION,
+            sanitize(&score.variant),
+            optional(score.clicks.map(|c| c.left)),
+            optional(score.clicks.map(|c| c.right)),
+            optional(score.clicks.map(|c| c.chord))
+        )?;
+        Ok(())
+    }
+
+    fn get_top_10_scores(
+        &self,
+        difficulty: &str,
+        variant: &str,
+        ranking: Ranking,
+    ) -> Result<Vec<HighScore>, StoreError> {
+        Ok(super::top_10(
+            self.load()?.iter(),
+            difficulty,
+            variant,
+            ranking,
+        ))
+    }
+
+    fn record_game(&self, game: &GameRecord) -> Result<(), StoreError> {
+        let path = self.games_path();
+        let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+        if file.metadata()?.len() == 0 {
+            writeln!(file, "{}", GAMES_HEADER)?;
+        }
+        writeln!(
+            file,
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            game.played_at,
+            sanitize(&game.difficulty),
+            game.width,
+            game.height,
+            game.mines,
+            game.won as u8,
+            game.time,
+            optional(game.seed),
+            game.bbbv,
+            game.clicks,
+            sanitize(&game.variant),
+            game.ranked as u8
+        )?;
+        Ok(())
+    }
+
+    fn games(&self) -> Result<Vec<GameRecord>, StoreError> {
+        let path = self.games_path();
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        contents
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty() && !line.starts_with('#'))
+            .map(|(i, line)| {
+                parse_game(line)
+                    .ok_or_else(|| StoreError::Corrupt(format!("{}:{}", path.display(), i + 1)))
+            })
+            .collect()
+    }
+
+    fn describe(&self) -> String {
+        format!("file {}", self.path.display())
+    }
+}
@@ -0,0 +1,49 @@
+//db.memory_store.rs
+use super::{GameRecord, HighScore, Ranking, ScoreEntry, ScoreStore, StoreError};
+use std::sync::Mutex;
+
+/// Keeps scores for the lifetime of the process only. Used in tests and as
+/// the last resort when nothing else can be opened.
+#[derive(Default)]
+pub struct MemoryStore {
+    scores: Mutex<Vec<HighScore>>,
+    games: Mutex<Vec<GameRecord>>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl ScoreStore for MemoryStore {
+    fn add_high_score(&self, score: &ScoreEntry) -> Result<(), StoreError> {
+        let mut scores = self.scores.lock().unwrap();
+        let id = scores.len() as i32 + 1;
+        scores.push(score.to_high_score(id));
+        Ok(())
+    }
+
+    fn get_top_10_scores(
+        &self,
+        difficulty: &str,
+        variant: &str,
+        ranking: Ranking,
+    ) -> Result<Vec<HighScore>, StoreError> {
+        let scores = self.scores.lock().unwrap();
+        Ok(super::top_10(scores.iter(), difficulty, variant, ranking))
+    }
+
+    fn record_game(&self, game: &GameRecord) -> Result<(), StoreError> {
+        self.games.lock().unwrap().push(game.clone());
+        Ok(())
+    }
+
+    fn games(&self) -> Result<Vec<GameRecord>, StoreError> {
+        Ok(self.games.lock().unwrap().clone())
+    }
+
+    fn describe(&self) -> String {
+        "in-memory (not saved)".to_string()
+    }
+}
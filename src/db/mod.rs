@@ -1,55 +1,492 @@
 //db.mod.rs
-use mysql::prelude::*;
-use mysql::{params, Pool};
+mod file_store;
+mod memory_store;
+mod mysql_store;
 
-pub struct DbConnection {
-    pool: Pool,
-}
+pub use file_store::FileStore;
+pub use memory_store::MemoryStore;
+pub use mysql_store::MySqlStore;
 
-impl DbConnection {
-    pub fn new(url: &str) -> Result<Self, mysql::Error> {
-        let pool = Pool::new(url)?;
-        Ok(Self { pool })
-    }
+use minesweeper::engine::{Board, Clicks, GameStatus, Metrics, Topology};
+use std::fmt;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Recorded with every score so rows can be traced back to a release.
+pub const CLIENT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Somewhere high scores can be kept. The GUI only talks to this trait, so
+/// the leaderboard works the same whichever backend was picked at startup.
+pub trait ScoreStore {
+    fn add_high_score(&self, score: &ScoreEntry) -> Result<(), StoreError>;
 
-    pub fn add_high_score(
+    fn get_top_10_scores(
         &self,
-        name: &str,
-        time: f32,
         difficulty: &str,
-    ) -> Result<(), mysql::Error> {
-        let mut conn = self.pool.get_conn()?;
-        conn.exec_drop(
-            "INSERT INTO high_scores (name, time, difficulty) VALUES (:name, :time, :difficulty)",
-            params! {
-                "name" => name,
-                "time" => time,
-                "difficulty" => difficulty,
-            },
-        )
-    }
+        variant: &str,
+        ranking: Ranking,
+    ) -> Result<Vec<HighScore>, StoreError>;
 
-    pub fn get_top_10_scores(&self, difficulty: &str) -> Result<Vec<HighScore>, mysql::Error> {
-        let mut conn = self.pool.get_conn()?;
-        conn.exec_map(
-            "SELECT id, name, time, difficulty FROM high_scores WHERE difficulty = :difficulty ORDER BY time ASC LIMIT 10",
-            params! {
-                "difficulty" => difficulty,
-            },
-            |(id, name, time, difficulty)| HighScore {
-                id,
-                name,
-                time,
-                difficulty,
-            },
-        )
-    }
+    /// Logs a finished game, won or lost, for the statistics screen.
+    fn record_game(&self, game: &GameRecord) -> Result<(), StoreError>;
+
+    /// Every logged game, oldest first.
+    fn games(&self) -> Result<Vec<GameRecord>, StoreError>;
+
+    /// Short human readable description, e.g. for the high score window.
+    fn describe(&self) -> String;
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct HighScore {
     pub id: i32,
     pub name: String,
     pub time: f32,
     pub difficulty: String,
-}
\ No newline at end of file
+    pub variant: String,
+    /// Missing for scores set before they were recorded.
+    pub bbbv: Option<u32>,
+    pub clicks: Option<Clicks>,
+}
+
+impl HighScore {
+    pub fn metrics(&self) -> Option<Metrics> {
+        Some(Metrics {
+            bbbv: self.bbbv?,
+            seconds: self.time,
+            clicks: self.clicks?,
+        })
+    }
+}
+
+/// A won game being submitted to the leaderboard.
+#[derive(Debug, Clone)]
+pub struct ScoreEntry {
+    pub name: String,
+    pub time: f32,
+    pub difficulty: String,
+    /// Seconds since the Unix epoch.
+    pub played_at: u64,
+    pub width: usize,
+    pub height: usize,
+    pub mines: usize,
+    pub seed: Option<u64>,
+    pub bbbv: Option<u32>,
+    pub clicks: Option<Clicks>,
+    pub variant: String,
+}
+
+impl ScoreEntry {
+    pub fn new(name: &str, time: f32, board: &Board) -> Self {
+        Self {
+            name: name.to_string(),
+            time,
+            difficulty: board.difficulty().name().to_string(),
+            played_at: now(),
+            width: board.width(),
+            height: board.height(),
+            mines: board.mine_count(),
+            seed: Some(board.seed()),
+            bbbv: Some(board.bbbv()),
+            clicks: Some(board.clicks()),
+            variant: variant(board),
+        }
+    }
+
+    fn to_high_score(&self, id: i32) -> HighScore {
+        HighScore {
+            id,
+            name: self.name.clone(),
+            time: self.time,
+            difficulty: self.difficulty.clone(),
+            variant: self.variant.clone(),
+            bbbv: self.bbbv,
+            clicks: self.clicks,
+        }
+    }
+}
+
+/// What a leaderboard is sorted by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ranking {
+    Time,
+    BbbvPerSecond,
+    Efficiency,
+}
+
+impl Ranking {
+    pub const ALL: [Ranking; 3] = [Ranking::Time, Ranking::BbbvPerSecond, Ranking::Efficiency];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Ranking::Time => "Time",
+            Ranking::BbbvPerSecond => "3BV/s",
+            Ranking::Efficiency => "Efficiency",
+        }
+    }
+
+    /// Sort key, lower is better. `None` when the score predates the
+    /// measure, which keeps it off that leaderboard.
+    fn key(&self, score: &HighScore) -> Option<f32> {
+        match self {
+            Ranking::Time => Some(score.time),
+            Ranking::BbbvPerSecond => {
+                let bbbv = score.bbbv? as f32;
+                (score.time > 0.0).then(|| -bbbv / score.time)
+            }
+            Ranking::Efficiency => score.metrics()?.efficiency().map(|v| -v),
+        }
+    }
+
+    /// The score as shown on this leaderboard.
+    pub fn format(&self, score: &HighScore) -> String {
+        match (self, self.key(score)) {
+            (Ranking::BbbvPerSecond, Some(key)) => {
+                format!("{:.2} 3BV/s ({:.2}s)", -key, score.time)
+            }
+            (Ranking::Efficiency, Some(key)) => {
+                format!("{:.0}% ({:.2}s)", -key * 100.0, score.time)
+            }
+            _ => format!("{:.2}s", score.time),
+        }
+    }
+}
+
+/// Whether `entry` would make the top 10 on any leaderboard.
+pub fn qualifies(store: &dyn ScoreStore, entry: &ScoreEntry) -> bool {
+    let candidate = entry.to_high_score(0);
+    Ranking::ALL.iter().any(|ranking| {
+        let Some(key) = ranking.key(&candidate) else {
+            return false;
+        };
+        store
+            .get_top_10_scores(&entry.difficulty, &entry.variant, *ranking)
+            .map(|scores| {
+                scores.len() < 10
+                    || scores
+                        .last()
+                        .and_then(|last| ranking.key(last))
+                        .is_some_and(|last| key < last)
+            })
+            .unwrap_or(false)
+    })
+}
+
+/// A finished game for the statistics screen.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameRecord {
+    /// Seconds since the Unix epoch.
+    pub played_at: u64,
+    pub difficulty: String,
+    pub width: usize,
+    pub height: usize,
+    pub mines: usize,
+    pub won: bool,
+    pub time: f32,
+    pub seed: Option<u64>,
+    /// 3BV of the whole board, also for lost games.
+    pub bbbv: u32,
+    pub clicks: u32,
+    pub variant: String,
+    /// Unranked games, e.g. practice or imported positions, are logged too
+    /// but can be left out of the statistics.
+    pub ranked: bool,
+}
+
+impl GameRecord {
+    pub fn new(time: f32, board: &Board, ranked: bool) -> Self {
+        Self {
+            played_at: now(),
+            difficulty: board.difficulty().name().to_string(),
+            width: board.width(),
+            height: board.height(),
+            mines: board.mine_count(),
+            won: board.status() == GameStatus::Won,
+            time,
+            seed: Some(board.seed()),
+            bbbv: board.bbbv(),
+            clicks: board.clicks().total(),
+            variant: variant(board),
+            ranked,
+        }
+    }
+
+    /// 3BV per click; 1.0 means no click was wasted. Only meaningful for
+    /// won games.
+    pub fn efficiency(&self) -> Option<f32> {
+        (self.won && self.clicks > 0).then(|| self.bbbv as f32 / self.clicks as f32)
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Leaderboard category on top of the difficulty, e.g. `no-guess`. Scores
+/// from different variants are never ranked against each other.
+pub fn variant(board: &Board) -> String {
+    let mut parts = Vec::new();
+    if board.topology() != Topology::FLAT {
+        parts.push(board.topology().name().to_string());
+    }
+    if board.mines_per_cell() > 1 {
+        parts.push(format!("stack-{}", board.mines_per_cell()));
+    }
+    if board.is_no_guess() {
+        parts.push("no-guess".to_string());
+    }
+    if let Some(day) = board.daily() {
+        parts.push(format!("daily-{}", day));
+    }
+    if parts.is_empty() {
+        "classic".to_string()
+    } else {
+        parts.join("+")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Backend {
+    MySql(String),
+    File(PathBuf),
+    Memory,
+}
+
+impl Backend {
+    /// The embedded backend: a scores file in the user data directory, or
+    /// memory only when there is no usable home directory.
+    pub fn local() -> Self {
+        match FileStore::default_path() {
+            Some(path) => Backend::File(path),
+            None => Backend::Memory,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum StoreError {
+    MySql(mysql::Error),
+    Io(std::io::Error),
+    Corrupt(String),
+}
+
+impl fmt::Display for StoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StoreError::MySql(e) => write!(f, "MySQL error: {}", e),
+            StoreError::Io(e) => write!(f, "I/O error: {}", e),
+            StoreError::Corrupt(msg) => write!(f, "Corrupt score data: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for StoreError {}
+
+impl From<mysql::Error> for StoreError {
+    fn from(e: mysql::Error) -> Self {
+        StoreError::MySql(e)
+    }
+}
+
+impl From<std::io::Error> for StoreError {
+    fn from(e: std::io::Error) -> Self {
+        StoreError::Io(e)
+    }
+}
+
+pub fn open(backend: &Backend) -> Result<Box<dyn ScoreStore>, StoreError> {
+    Ok(match backend {
+        Backend::MySql(url) => Box::new(MySqlStore::new(url)?),
+        Backend::File(path) => Box::new(FileStore::new(path.clone())?),
+        Backend::Memory => Box::new(MemoryStore::new()),
+    })
+}
+
+/// Opens `backend`, falling back to the local file store and finally to
+/// memory so the leaderboard is always available.
+pub fn open_or_fallback(backend: &Backend) -> Box<dyn ScoreStore> {
+    let mut candidates = vec![backend.clone(), Backend::local(), Backend::Memory];
+    candidates.dedup();
+    for candidate in &candidates {
+        match open(candidate) {
+            Ok(store) => return store,
+            Err(e) => eprintln!("Score store {:?} unavailable: {}", candidate, e),
+        }
+    }
+    Box::new(MemoryStore::new())
+}
+
+/// Shared ranking for the backends that don't sort in a query.
+fn top_10<'a>(
+    scores: impl Iterator<Item = &'a HighScore>,
+    difficulty: &str,
+    variant: &str,
+    ranking: Ranking,
+) -> Vec<HighScore> {
+    let mut matching: Vec<(f32, HighScore)> = scores
+        .filter(|s| s.difficulty == difficulty && s.variant == variant)
+        .filter_map(|s| Some((ranking.key(s)?, s.clone())))
+        .collect();
+    matching.sort_by(|a, b| a.0.total_cmp(&b.0));
+    matching.into_iter().take(10).map(|(_, s)| s).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn entry(name: &str, time: f32, difficulty: &str, variant: &str) -> ScoreEntry {
+        ScoreEntry {
+            name: name.to_string(),
+            time,
+            difficulty: difficulty.to_string(),
+            played_at: 1_700_000_000,
+            width: 8,
+            height: 8,
+            mines: 10,
+            seed: Some(3),
+            bbbv: Some(20),
+            clicks: Some(Clicks {
+                left: 20,
+                right: 5,
+                chord: 0,
+            }),
+            variant: variant.to_string(),
+        }
+    }
+
+    fn game(won: bool, time: f32, ranked: bool) -> GameRecord {
+        GameRecord {
+            played_at: 1_700_000_000,
+            difficulty: "Easy".to_string(),
+            width: 8,
+            height: 8,
+            mines: 10,
+            won,
+            time,
+            seed: Some(3),
+            bbbv: 20,
+            clicks: 25,
+            variant: "classic".to_string(),
+            ranked,
+        }
+    }
+
+    /// What every backend has to do the same way.
+    fn check_contract(store: &dyn ScoreStore) {
+        for (name, time) in [("Cy", 30.0), ("Ada", 10.5), ("Bo", 20.25)] {
+            store
+                .add_high_score(&entry(name, time, "Easy", "classic"))
+                .unwrap();
+        }
+        store
+            .add_high_score(&entry("Lucky", 1.0, "Easy", "no-guess"))
+            .unwrap();
+        store
+            .add_high_score(&entry("Med", 2.0, "Medium", "classic"))
+            .unwrap();
+
+        let top = store
+            .get_top_10_scores("Easy", "classic", Ranking::Time)
+            .unwrap();
+        let names: Vec<&str> = top.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["Ada", "Bo", "Cy"]);
+        assert_eq!(top[0].time, 10.5);
+        assert_eq!(top[0].bbbv, Some(20));
+        assert_eq!(top[0].clicks.map(|c| c.total()), Some(25));
+
+        let fastest = store
+            .get_top_10_scores("Easy", "classic", Ranking::BbbvPerSecond)
+            .unwrap();
+        assert_eq!(fastest[0].name, "Ada");
+        let no_guess = store
+            .get_top_10_scores("Easy", "no-guess", Ranking::Time)
+            .unwrap();
+        assert_eq!(no_guess.len(), 1);
+
+        for i in 0..12 {
+            store
+                .add_high_score(&entry("Many", 40.0 + i as f32, "Hard", "classic"))
+                .unwrap();
+        }
+        let hard = store
+            .get_top_10_scores("Hard", "classic", Ranking::Time)
+            .unwrap();
+        assert_eq!(hard.len(), 10);
+        assert!(qualifies(store, &entry("New", 45.0, "Hard", "classic")));
+        assert!(!qualifies(store, &entry("Slow", 99.0, "Hard", "classic")));
+
+        store.record_game(&game(false, 3.0, true)).unwrap();
+        store.record_game(&game(true, 12.0, false)).unwrap();
+        assert_eq!(
+            store.games().unwrap(),
+            vec![game(false, 3.0, true), game(true, 12.0, false)]
+        );
+    }
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("minesweeper-test-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn memory_store_keeps_the_contract() {
+        check_contract(&MemoryStore::new());
+    }
+
+    #[test]
+    fn file_store_keeps_the_contract_across_reopening() {
+        let dir = scratch_dir("contract");
+        let path = dir.join("high_scores.tsv");
+        check_contract(&FileStore::new(path.clone()).unwrap());
+
+        let reopened = FileStore::new(path).unwrap();
+        let top = reopened
+            .get_top_10_scores("Easy", "classic", Ranking::Time)
+            .unwrap();
+        assert_eq!(top.len(), 3);
+        assert_eq!(reopened.games().unwrap().len(), 2);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn file_store_upgrades_old_rows() {
+        let dir = scratch_dir("upgrade");
+        let path = dir.join("high_scores.tsv");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(&path, "1\tAda\t12.5\tEasy\n").unwrap();
+        let store = FileStore::new(path).unwrap();
+        let top = store
+            .get_top_10_scores("Easy", "classic", Ranking::Time)
+            .unwrap();
+        assert_eq!(top.len(), 1);
+        assert_eq!((top[0].name.as_str(), top[0].time), ("Ada", 12.5));
+        assert_eq!(top[0].bbbv, None);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn file_store_marks_older_games_ranked() {
+        let dir = scratch_dir("games-upgrade");
+        let path = dir.join("high_scores.tsv");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("games.tsv"),
+            "# minesweeper games v1\n1700000000\tEasy\t8\t8\t10\t0\t3\t3\t20\t25\tclassic\n",
+        )
+        .unwrap();
+        let store = FileStore::new(path).unwrap();
+        store.record_game(&game(true, 12.0, false)).unwrap();
+        assert_eq!(
+            store.games().unwrap(),
+            vec![game(false, 3.0, true), game(true, 12.0, false)]
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,277 @@
+//db.mysql_store.rs
+use super::{GameRecord, HighScore, Ranking, ScoreEntry, ScoreStore, StoreError, CLIENT_VERSION};
+use minesweeper::engine::Clicks;
+use mysql::prelude::*;
+use mysql::{params, Pool, PooledConn};
+
+/// Schema changes in the order they were introduced; migration `n` is
+/// `MIGRATIONS[n - 1]`. Never edit an entry once released, append a new
+/// one instead.
+const MIGRATIONS: &[&[&str]] = &[
+    // 1: the original table. Older installs created it by hand, hence
+    // IF NOT EXISTS.
+    &["CREATE TABLE IF NOT EXISTS high_scores (
+        id INT AUTO_INCREMENT PRIMARY KEY,
+        name VARCHAR(255) NOT NULL,
+        time FLOAT NOT NULL,
+        difficulty VARCHAR(32) NOT NULL
+    )"],
+    // 2: when and on what board the score was set.
+    &[
+        "ALTER TABLE high_scores
+            ADD COLUMN played_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            ADD COLUMN width INT NULL,
+            ADD COLUMN height INT NULL,
+            ADD COLUMN mines INT NULL",
+        "CREATE INDEX high_scores_difficulty_time ON high_scores (difficulty, time)",
+    ],
+    // 3: enough to reproduce and rank the game.
+    &["ALTER TABLE high_scores
+        ADD COLUMN seed BIGINT UNSIGNED NULL,
+        ADD COLUMN bbbv INT NULL,
+        ADD COLUMN client_version VARCHAR(32) NULL"],
+    // 4: separate leaderboards for rule variants such as no-guess boards.
+    &["ALTER TABLE high_scores
+        ADD COLUMN variant VARCHAR(64) NOT NULL DEFAULT 'classic'"],
+    // 5: every finished game, won or lost, for statistics.
+    &["CREATE TABLE games (
+        id INT AUTO_INCREMENT PRIMARY KEY,
+        played_at DATETIME NOT NULL,
+        difficulty VARCHAR(32) NOT NULL,
+        width INT NOT NULL,
+        height INT NOT NULL,
+        mines INT NOT NULL,
+        won BOOLEAN NOT NULL,
+        time FLOAT NOT NULL,
+        seed BIGINT UNSIGNED NULL,
+        bbbv INT NOT NULL,
+        clicks INT NOT NULL,
+        variant VARCHAR(64) NOT NULL,
+        client_version VARCHAR(32) NULL,
+        INDEX games_played_at (played_at)
+    )"],
+    // 6: click counts, so leaderboards can rank by 3BV/s and efficiency.
+    &["ALTER TABLE high_scores
+        ADD COLUMN left_clicks INT NULL,
+        ADD COLUMN right_clicks INT NULL,
+        ADD COLUMN chord_clicks INT NULL"],
+    // 7: unranked games are logged too. Until now only ranked ones were.
+    &["ALTER TABLE games
+        ADD COLUMN ranked BOOLEAN NOT NULL DEFAULT TRUE"],
+];
+
+/// The leaderboard columns of a `high_scores` row.
+type ScoreRow = (
+    i32,
+    String,
+    f32,
+    String,
+    String,
+    Option<u32>,
+    Option<u32>,
+    Option<u32>,
+    Option<u32>,
+);
+
+/// A row of the `games` table in column order.
+type GameRow = (
+    u64,
+    String,
+    u32,
+    u32,
+    u32,
+    bool,
+    f32,
+    Option<u64>,
+    u32,
+    u32,
+    String,
+    bool,
+);
+
+pub struct MySqlStore {
+    pool: Pool,
+}
+
+impl MySqlStore {
+    pub fn new(url: &str) -> Result<Self, mysql::Error> {
+        let pool = Pool::new(url)?;
+        // Connecting here also surfaces a bad URL or password at startup.
+        let mut conn = pool.get_conn()?;
+        migrate(&mut conn)?;
+        Ok(Self { pool })
+    }
+}
+
+/// Creates the schema on first connect and applies any migrations newer
+/// than the version recorded in `schema_version`.
+///
+/// DDL commits on its own in MySQL, so a migration that fails halfway
+/// cannot be rolled back. Each statement is logged in `schema_step` as it
+/// succeeds and skipped on the next attempt, so a retry resumes where the
+/// failed one stopped.
+fn migrate(conn: &mut PooledConn) -> Result<(), mysql::Error> {
+    conn.query_drop(
+        "CREATE TABLE IF NOT EXISTS schema_version (
+            version INT NOT NULL PRIMARY KEY,
+            applied_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+        )",
+    )?;
+    conn.query_drop(
+        "CREATE TABLE IF NOT EXISTS schema_step (
+            version INT NOT NULL,
+            step INT NOT NULL,
+            PRIMARY KEY (version, step)
+        )",
+    )?;
+    // MAX() over an empty table is a single NULL row.
+    let current = conn
+        .query_first::<Option<u32>, _>("SELECT MAX(version) FROM schema_version")?
+        .flatten()
+        .unwrap_or(0) as usize;
+
+    for (i, statements) in MIGRATIONS.iter().enumerate().skip(current) {
+        let version = i as u32 + 1;
+        let done: Vec<u32> = conn.exec(
+            "SELECT step FROM schema_step WHERE version = :version",
+            params! { "version" => version },
+        )?;
+        for (step, statement) in statements.iter().enumerate() {
+            let step = step as u32;
+            if done.contains(&step) {
+                continue;
+            }
+            conn.query_drop(*statement)?;
+            conn.exec_drop(
+                "INSERT INTO schema_step (version, step) VALUES (:version, :step)",
+                params! { "version" => version, "step" => step },
+            )?;
+        }
+        conn.exec_drop(
+            "INSERT INTO schema_version (version) VALUES (:version)",
+            params! { "version" => version },
+        )?;
+    }
+    Ok(())
+}
+
+impl ScoreStore for MySqlStore {
+    fn add_high_score(&self, score: &ScoreEntry) -> Result<(), StoreError> {
+        let mut conn = self.pool.get_conn()?;
+        conn.exec_drop(
+            "INSERT INTO high_scores (name, time, difficulty, played_at, width, height, mines, seed, bbbv, client_version, variant, left_clicks, right_clicks, chord_clicks)
+             VALUES (:name, :time, :difficulty, FROM_UNIXTIME(:played_at), :width, :height, :mines, :seed, :bbbv, :client_version, :variant, :left_clicks, :right_clicks, :chord_clicks)",
+            params! {
+                "name" => &score.name,
+                "time" => score.time,
+                "difficulty" => &score.difficulty,
+                "played_at" => score.played_at,
+                "width" => score.width as u32,
+                "height" => score.height as u32,
+                "mines" => score.mines as u32,
+                "seed" => score.seed,
+                "bbbv" => score.bbbv,
+                "client_version" => CLIENT_VERSION,
+                "variant" => &score.variant,
+                "left_clicks" => score.clicks.map(|c| c.left),
+                "right_clicks" => score.clicks.map(|c| c.right),
+                "chord_clicks" => score.clicks.map(|c| c.chord),
+            },
+        )?;
+        Ok(())
+    }
+
+    fn get_top_10_scores(
+        &self,
+        difficulty: &str,
+        variant: &str,
+        ranking: Ranking,
+    ) -> Result<Vec<HighScore>, StoreError> {
+        let order = match ranking {
+            Ranking::Time => "time ASC",
+            Ranking::BbbvPerSecond => "bbbv IS NULL, bbbv / NULLIF(time, 0) IS NULL, bbbv / time DESC",
+            Ranking::Efficiency => {
+                "left_clicks IS NULL, bbbv / NULLIF(left_clicks + right_clicks + chord_clicks, 0) IS NULL, bbbv / (left_clicks + right_clicks + chord_clicks) DESC"
+            }
+        };
+        let mut conn = self.pool.get_conn()?;
+        let scores = conn.exec_map(
+            format!(
+                "SELECT id, name, time, difficulty, variant, bbbv, left_clicks, right_clicks, chord_clicks FROM high_scores WHERE difficulty = :difficulty AND variant = :variant ORDER BY {} LIMIT 10",
+                order
+            ),
+            params! {
+                "difficulty" => difficulty,
+                "variant" => variant,
+            },
+            |(id, name, time, difficulty, variant, bbbv, left, right, chord): ScoreRow| HighScore {
+                id,
+                name,
+                time,
+                difficulty,
+                variant,
+                bbbv,
+                clicks: match (left, right, chord) {
+                    (Some(left), Some(right), Some(chord)) => Some(Clicks { left, right, chord }),
+                    _ => None,
+                },
+            },
+        )?;
+        // Rows the ranking cannot place sort last; drop them like the other
+        // backends do.
+        Ok(scores
+            .into_iter()
+            .filter(|score| ranking.key(score).is_some())
+            .collect())
+    }
+
+    fn record_game(&self, game: &GameRecord) -> Result<(), StoreError> {
+        let mut conn = self.pool.get_conn()?;
+        conn.exec_drop(
+            "INSERT INTO games (played_at, difficulty, width, height, mines, won, time, seed, bbbv, clicks, variant, ranked, client_version)
+             VALUES (FROM_UNIXTIME(:played_at), :difficulty, :width, :height, :mines, :won, :time, :seed, :bbbv, :clicks, :variant, :ranked, :client_version)",
+            params! {
+                "played_at" => game.played_at,
+                "difficulty" => &game.difficulty,
+                "width" => game.width as u32,
+                "height" => game.height as u32,
+                "mines" => game.mines as u32,
+                "won" => game.won,
+                "time" => game.time,
+                "seed" => game.seed,
+                "bbbv" => game.bbbv,
+                "clicks" => game.clicks,
+                "variant" => &game.variant,
+                "ranked" => game.ranked,
+                "client_version" => CLIENT_VERSION,
+            },
+        )?;
+        Ok(())
+    }
+
+    fn games(&self) -> Result<Vec<GameRecord>, StoreError> {
+        let mut conn = self.pool.get_conn()?;
+        let games = conn.query_map(
+            "SELECT UNIX_TIMESTAMP(played_at), difficulty, width, height, mines, won, time, seed, bbbv, clicks, variant, ranked FROM games ORDER BY played_at, id",
+            |(played_at, difficulty, width, height, mines, won, time, seed, bbbv, clicks, variant, ranked): GameRow| GameRecord {
+                played_at,
+                difficulty,
+                width: width as usize,
+                height: height as usize,
+                mines: mines as usize,
+                won,
+                time,
+                seed,
+                bbbv,
+                clicks,
+                variant,
+                ranked,
+            },
+        )?;
+        Ok(games)
+    }
+
+    fn describe(&self) -> String {
+        "MySQL".to_string()
+    }
+}
@@ -0,0 +1,101 @@
+//engine.daily.rs
+//! The daily challenge: one board per UTC day, identical for every player
+//! on the same version of the game.
+
+use super::{Board, Difficulty};
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A UTC calendar day, counted from 1970-01-01.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Day(pub u32);
+
+impl Day {
+    pub const DIFFICULTY: Difficulty = Difficulty::Medium;
+
+    pub fn today() -> Self {
+        let seconds = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        Day((seconds / 86_400) as u32)
+    }
+
+    pub fn seed(self) -> u64 {
+        splitmix64(self.0 as u64 ^ 0x6461_696c_795f_6d73)
+    }
+
+    /// The cell opened for the player when the daily starts, so everyone
+    /// begins from the same opening.
+    pub fn first_click(self) -> (usize, usize) {
+        let (width, height, _) = Self::DIFFICULTY.dimensions();
+        let bits = splitmix64(self.seed());
+        (
+            (bits % width as u64) as usize,
+            ((bits >> 32) % height as u64) as usize,
+        )
+    }
+
+    /// The day's board, not yet opened.
+    pub fn board(self) -> Board {
+        let (width, height, mines) = Self::DIFFICULTY.dimensions();
+        let mut board = Board::with_seed(width, height, mines, self.seed());
+        board.daily = Some(self);
+        board
+    }
+
+    /// Year, month and day of the month.
+    pub fn date(self) -> (i64, u32, u32) {
+        // Howard Hinnant's days-to-civil algorithm.
+        let z = self.0 as i64 + 719_468;
+        let era = z.div_euclid(146_097);
+        let doe = z - era * 146_097;
+        let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+        let year = yoe + era * 400 + i64::from(month <= 2);
+        (year, month, day)
+    }
+}
+
+/// `YYYY-MM-DD`.
+impl fmt::Display for Day {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (year, month, day) = self.date();
+        write!(f, "{:04}-{:02}-{:02}", year, month, day)
+    }
+}
+
+fn splitmix64(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn days_print_as_dates() {
+        assert_eq!(Day(0).to_string(), "1970-01-01");
+        assert_eq!(Day(19_723).to_string(), "2024-01-01");
+        assert_eq!(Day(19_782).to_string(), "2024-02-29");
+    }
+
+    #[test]
+    fn every_player_gets_the_same_board() {
+        let opened = |day: Day| {
+            let mut board = day.board();
+            let (x, y) = day.first_click();
+            board.reveal(x, y).unwrap();
+            assert_eq!(board.daily(), Some(day));
+            board.mine_positions()
+        };
+        assert_eq!(opened(Day(19_723)), opened(Day(19_723)));
+        assert_ne!(opened(Day(19_723)), opened(Day(19_724)));
+    }
+}
@@ -0,0 +1,209 @@
+//engine.difficulty.rs
+use super::Topology;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+    Custom {
+        width: usize,
+        height: usize,
+        mines: usize,
+    },
+}
+
+impl Difficulty {
+    pub const PRESETS: [Difficulty; 3] = [Difficulty::Easy, Difficulty::Medium, Difficulty::Hard];
+
+    pub const MIN_SIDE: usize = 5;
+    pub const MAX_SIDE: usize = 100;
+
+    /// A validated custom board.
+    pub fn custom(width: usize, height: usize, mines: usize) -> Result<Self, String> {
+        let difficulty = Difficulty::Custom {
+            width,
+            height,
+            mines,
+        };
+        difficulty.validate()?;
+        Ok(difficulty)
+    }
+
+    /// Most mines a `width` x `height` board can hold while still keeping
+    /// the first click and its neighbours free, wherever that click lands.
+    pub fn max_mines(width: usize, height: usize, topology: Topology, mines_per_cell: u8) -> usize {
+        let opening = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| 1 + topology.neighbors(width, height, x, y).count())
+            .max()
+            .unwrap_or(0);
+        (width * height).saturating_sub(opening) * mines_per_cell as usize
+    }
+
+    /// Checks the board for the classic game: flat, one mine per cell.
+    pub fn validate(&self) -> Result<(), String> {
+        self.validate_for(Topology::FLAT, 1)
+    }
+
+    /// Checks the board for a game on `topology` with up to
+    /// `mines_per_cell` mines in a cell.
+    pub fn validate_for(&self, topology: Topology, mines_per_cell: u8) -> Result<(), String> {
+        let (width, height, mines) = self.dimensions();
+        let sides = Difficulty::MIN_SIDE..=Difficulty::MAX_SIDE;
+        if !sides.contains(&width) || !sides.contains(&height) {
+            return Err(format!(
+                "width and height must be between {} and {}",
+                Difficulty::MIN_SIDE,
+                Difficulty::MAX_SIDE
+            ));
+        }
+        let max_mines = Difficulty::max_mines(width, height, topology, mines_per_cell);
+        if max_mines == 0 {
+            return Err(format!(
+                "a {}x{} board has no room for mines on this grid",
+                width, height
+            ));
+        }
+        if mines == 0 || mines > max_mines {
+            return Err(format!(
+                "a {}x{} board needs between 1 and {} mines",
+                width, height, max_mines
+            ));
+        }
+        Ok(())
+    }
+
+    /// `(width, height, mines)` for this difficulty.
+    pub fn dimensions(&self) -> (usize, usize, usize) {
+        match *self {
+            Difficulty::Easy => (8, 8, 10),
+            Difficulty::Medium => (16, 16, 40),
+            Difficulty::Hard => (30, 16, 99),
+            Difficulty::Custom {
+                width,
+                height,
+                mines,
+            } => (width, height, mines),
+        }
+    }
+
+    /// Maps board dimensions back to a preset where one matches.
+    pub fn from_dimensions(width: usize, height: usize, mines: usize) -> Self {
+        Difficulty::PRESETS
+            .into_iter()
+            .find(|d| d.dimensions() == (width, height, mines))
+            .unwrap_or(Difficulty::Custom {
+                width,
+                height,
+                mines,
+            })
+    }
+
+    /// Leaderboard category name.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Medium => "Medium",
+            Difficulty::Hard => "Hard",
+            Difficulty::Custom { .. } => "Custom",
+        }
+    }
+
+    /// The `WIDTHxHEIGHT:MINES` form accepted by `from_str`.
+    pub fn spec(&self) -> String {
+        let (width, height, mines) = self.dimensions();
+        format!("{}x{}:{}", width, height, mines)
+    }
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (width, height, mines) = self.dimensions();
+        write!(f, "{} ({}x{}, {} mines)", self.name(), width, height, mines)
+    }
+}
+
+/// Parses `easy`, `medium`, `hard` or a custom `WIDTHxHEIGHT:MINES`.
+impl FromStr for Difficulty {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "easy" => Ok(Difficulty::Easy),
+            "medium" => Ok(Difficulty::Medium),
+            "hard" => Ok(Difficulty::Hard),
+            custom => {
+                let parse = || -> Option<Difficulty> {
+                    let (size, mines) = custom.split_once(':')?;
+                    let (width, height) = size.split_once('x')?;
+                    Some(Difficulty::Custom {
+                        width: width.trim().parse().ok()?,
+                        height: height.trim().parse().ok()?,
+                        mines: mines.trim().parse().ok()?,
+                    })
+                };
+                let difficulty = parse().ok_or_else(|| {
+                    format!(
+                        "expected easy, medium, hard or WIDTHxHEIGHT:MINES, got {:?}",
+                        s
+                    )
+                })?;
+                difficulty.validate()?;
+                Ok(difficulty)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{Board, GameError};
+
+    #[test]
+    fn max_mines_leaves_the_largest_opening_free() {
+        assert_eq!(Difficulty::max_mines(10, 10, Topology::FLAT, 1), 91);
+        assert_eq!(Difficulty::max_mines(10, 10, Topology::CROSS, 1), 95);
+        assert_eq!(Difficulty::max_mines(10, 10, Topology::RADIUS_2, 1), 75);
+        assert_eq!(Difficulty::max_mines(10, 10, Topology::FLAT, 3), 273);
+    }
+
+    #[test]
+    fn validate_for_checks_the_grid() {
+        let small = Difficulty::custom(5, 5, 10).unwrap();
+        assert!(small.validate_for(Topology::FLAT, 1).is_ok());
+        assert!(small.validate_for(Topology::RADIUS_2, 1).is_err());
+        assert!(Difficulty::custom(5, 5, 17).is_err());
+        assert!(Difficulty::Custom {
+            width: 5,
+            height: 5,
+            mines: 30,
+        }
+        .validate_for(Topology::FLAT, 2)
+        .is_ok());
+    }
+
+    #[test]
+    fn boards_reject_mines_that_do_not_fit() {
+        let mut board = Board::with_seed(5, 5, 10, 1);
+        board.set_topology(Topology::RADIUS_2);
+        assert_eq!(
+            board.reveal(2, 2),
+            Err(GameError::TooManyMines { mines: 10, max: 0 })
+        );
+        assert!(!board.is_initialized());
+    }
+
+    #[test]
+    fn parses_presets_and_custom_specs() {
+        assert_eq!("Hard".parse(), Ok(Difficulty::Hard));
+        let custom: Difficulty = "12x9:20".parse().unwrap();
+        assert_eq!(custom.dimensions(), (12, 9, 20));
+        assert_eq!(custom.spec(), "12x9:20");
+        assert!("12x9".parse::<Difficulty>().is_err());
+        assert!("4x9:3".parse::<Difficulty>().is_err());
+    }
+}
@@ -0,0 +1,436 @@
+//engine.endless.rs
+//! A board with no edges. Mines are laid out one chunk at a time from the
+//! world seed and the chunk's position, so a chunk comes out the same
+//! whenever it is first needed and only the player's marks have to be
+//! stored. The run ends at the first mine; its score is how many safe
+//! cells were opened before that.
+
+use super::{Cell, CellState, GameStatus, Topology};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::HashMap;
+
+const MAGIC: &str = "minesweeper endless";
+const VERSION: u32 = 1;
+
+/// Cells along each side of a chunk.
+const CHUNK_SIZE: i64 = 16;
+
+/// Share of cells holding a mine, a little below expert.
+const DENSITY: f64 = 0.18;
+
+/// Most cells one click opens. Nothing bounds an opening in an endless
+/// world, so flood fill stops here; cells it did not get to stay closed
+/// and open with another click.
+const FLOOD_LIMIT: u64 = 10_000;
+
+#[derive(Debug, Clone)]
+struct Chunk {
+    /// Row by row, `CHUNK_SIZE` cells each.
+    mines: Vec<bool>,
+    states: Vec<CellState>,
+    /// Numbers of opened cells; only meaningful once revealed.
+    numbers: Vec<u8>,
+}
+
+impl Chunk {
+    fn generate(seed: u64, (cx, cy): (i64, i64)) -> Self {
+        let mut rng = StdRng::seed_from_u64(
+            seed ^ (cx as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+                ^ (cy as u64).wrapping_mul(0xC2B2_AE3D_27D4_EB4F),
+        );
+        let mines = (0..CHUNK_SIZE * CHUNK_SIZE)
+            .map(|i| {
+                let mine = rng.gen_bool(DENSITY);
+                let (x, y) = (
+                    cx * CHUNK_SIZE + i % CHUNK_SIZE,
+                    cy * CHUNK_SIZE + i / CHUNK_SIZE,
+                );
+                // The start and its neighbours are always clear.
+                mine && (x.abs() > 1 || y.abs() > 1)
+            })
+            .collect();
+        let cells = (CHUNK_SIZE * CHUNK_SIZE) as usize;
+        Chunk {
+            mines,
+            states: vec![CellState::Hidden; cells],
+            numbers: vec![0; cells],
+        }
+    }
+
+    fn is_untouched(&self) -> bool {
+        self.states.iter().all(|state| *state == CellState::Hidden)
+    }
+}
+
+/// The chunk holding `(x, y)` and the cell's index inside it.
+fn locate(x: i64, y: i64) -> ((i64, i64), usize) {
+    let chunk = (x.div_euclid(CHUNK_SIZE), y.div_euclid(CHUNK_SIZE));
+    let index = y.rem_euclid(CHUNK_SIZE) * CHUNK_SIZE + x.rem_euclid(CHUNK_SIZE);
+    (chunk, index as usize)
+}
+
+/// The eight cells around `(x, y)`, as on a flat board.
+fn neighbors(x: i64, y: i64) -> impl Iterator<Item = (i64, i64)> {
+    Topology::FLAT
+        .offsets(0, 0)
+        .iter()
+        .map(move |&(dx, dy)| (x + dx as i64, y + dy as i64))
+}
+
+/// An endless game. Cell coordinates can be negative; the run starts at
+/// `(0, 0)`, already opened.
+#[derive(Debug, Clone)]
+pub struct Endless {
+    seed: u64,
+    chunks: HashMap<(i64, i64), Chunk>,
+    status: GameStatus,
+    cleared: u64,
+    best: u64,
+}
+
+impl Endless {
+    pub fn new(seed: u64) -> Self {
+        let mut game = Endless {
+            seed,
+            chunks: HashMap::new(),
+            status: GameStatus::Playing,
+            cleared: 0,
+            best: 0,
+        };
+        game.reveal(0, 0);
+        game
+    }
+
+    /// A new world from `seed`, keeping the best score.
+    pub fn restart(&mut self, seed: u64) {
+        let best = self.best;
+        *self = Endless::new(seed);
+        self.best = self.best.max(best);
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// `Playing` until a mine is hit, then `Lost`.
+    pub fn status(&self) -> GameStatus {
+        self.status
+    }
+
+    /// Safe cells opened so far, the run's score.
+    pub fn cleared(&self) -> u64 {
+        self.cleared
+    }
+
+    /// The highest score of any run in this save.
+    pub fn best(&self) -> u64 {
+        self.best
+    }
+
+    /// Chunks laid out so far.
+    pub fn chunk_count(&self) -> usize {
+        self.chunks.len()
+    }
+
+    pub fn cell_state(&self, x: i64, y: i64) -> CellState {
+        let (chunk, i) = locate(x, y);
+        self.chunks
+            .get(&chunk)
+            .map_or(CellState::Hidden, |chunk| chunk.states[i].clone())
+    }
+
+    /// What an opened cell shows; `None` while it is closed.
+    pub fn cell(&self, x: i64, y: i64) -> Option<Cell> {
+        let (chunk, i) = locate(x, y);
+        let chunk = self.chunks.get(&chunk)?;
+        if chunk.states[i] != CellState::Revealed {
+            return None;
+        }
+        Some(match chunk.numbers[i] {
+            _ if chunk.mines[i] => Cell::Mine,
+            0 => Cell::Empty,
+            n => Cell::Number(n),
+        })
+    }
+
+    fn chunk(&mut self, chunk: (i64, i64)) -> &mut Chunk {
+        let seed = self.seed;
+        self.chunks
+            .entry(chunk)
+            .or_insert_with(|| Chunk::generate(seed, chunk))
+    }
+
+    fn is_mine(&mut self, x: i64, y: i64) -> bool {
+        let (chunk, i) = locate(x, y);
+        self.chunk(chunk).mines[i]
+    }
+
+    fn state(&mut self, x: i64, y: i64) -> &mut CellState {
+        let (chunk, i) = locate(x, y);
+        &mut self.chunk(chunk).states[i]
+    }
+
+    /// Opens a cell, flood-filling empty regions up to `FLOOD_LIMIT`
+    /// cells. Opening an already opened number chords it instead.
+    pub fn reveal(&mut self, x: i64, y: i64) {
+        if self.status != GameStatus::Playing {
+            return;
+        }
+        match self.cell_state(x, y) {
+            CellState::Revealed => return self.chord(x, y),
+            CellState::Flagged => return,
+            CellState::Hidden | CellState::Questioned => {}
+        }
+        let mut stack = vec![(x, y)];
+        let mut opened = 0;
+        while let Some((cx, cy)) = stack.pop() {
+            if opened == FLOOD_LIMIT {
+                return;
+            }
+            if matches!(
+                self.cell_state(cx, cy),
+                CellState::Revealed | CellState::Flagged
+            ) {
+                continue;
+            }
+            *self.state(cx, cy) = CellState::Revealed;
+            if self.is_mine(cx, cy) {
+                self.status = GameStatus::Lost;
+                self.reveal_known_mines();
+                return;
+            }
+            let number = neighbors(cx, cy)
+                .filter(|&(nx, ny)| self.is_mine(nx, ny))
+                .count() as u8;
+            let (chunk, i) = locate(cx, cy);
+            self.chunk(chunk).numbers[i] = number;
+            self.cleared += 1;
+            self.best = self.best.max(self.cleared);
+            opened += 1;
+            if number == 0 {
+                stack.extend(neighbors(cx, cy));
+            }
+        }
+    }
+
+    /// Opens the closed neighbours of an opened number once it has as
+    /// many flags around it.
+    pub fn chord(&mut self, x: i64, y: i64) {
+        let Some(Cell::Number(number)) = self.cell(x, y) else {
+            return;
+        };
+        let flags = neighbors(x, y)
+            .filter(|&(nx, ny)| self.cell_state(nx, ny) == CellState::Flagged)
+            .count();
+        if flags != number as usize {
+            return;
+        }
+        for (nx, ny) in neighbors(x, y) {
+            if self.cell_state(nx, ny) == CellState::Hidden {
+                self.reveal(nx, ny);
+            }
+        }
+    }
+
+    /// Flags a closed cell, or takes its flag away.
+    pub fn toggle_flag(&mut self, x: i64, y: i64) {
+        if self.status != GameStatus::Playing {
+            return;
+        }
+        let state = self.state(x, y);
+        *state = match state {
+            CellState::Hidden | CellState::Questioned => CellState::Flagged,
+            CellState::Flagged => CellState::Hidden,
+            CellState::Revealed => CellState::Revealed,
+        };
+    }
+
+    /// Shows every mine in the chunks laid out so far.
+    fn reveal_known_mines(&mut self) {
+        for chunk in self.chunks.values_mut() {
+            for (state, &mine) in chunk.states.iter_mut().zip(&chunk.mines) {
+                if mine {
+                    *state = CellState::Revealed;
+                }
+            }
+        }
+    }
+
+    /// The seed, scores and every chunk the player has marked or opened.
+    /// Untouched chunks are laid out again from the seed when needed.
+    pub fn encode(&self) -> String {
+        let mut out = format!("{} v{}\n", MAGIC, VERSION);
+        out += &format!("seed {}\n", self.seed);
+        out += &format!(
+            "status {}\n",
+            match self.status {
+                GameStatus::Lost => "lost",
+                _ => "playing",
+            }
+        );
+        out += &format!("best {}\n", self.best);
+        let mut touched: Vec<_> = self
+            .chunks
+            .iter()
+            .filter(|(_, chunk)| !chunk.is_untouched())
+            .collect();
+        touched.sort_by_key(|(&position, _)| position);
+        for ((cx, cy), chunk) in touched {
+            out += &format!("chunk {} {}\n", cx, cy);
+            for row in chunk.states.chunks(CHUNK_SIZE as usize) {
+                out.extend(row.iter().map(|state| match state {
+                    CellState::Hidden | CellState::Questioned => 'h',
+                    CellState::Revealed => 'r',
+                    CellState::Flagged => 'f',
+                }));
+                out.push('\n');
+            }
+        }
+        out
+    }
+
+    pub fn decode(text: &str) -> Result<Self, String> {
+        let mut lines = text.lines();
+        let version = lines
+            .next()
+            .and_then(|line| line.strip_prefix(MAGIC))
+            .and_then(|rest| rest.trim().strip_prefix('v'))
+            .and_then(|v| v.parse::<u32>().ok())
+            .ok_or("not a minesweeper endless save")?;
+        if version != VERSION {
+            return Err(format!("unsupported endless save version {}", version));
+        }
+
+        let mut field = |name: &str| -> Result<String, String> {
+            let line = lines.next().ok_or(format!("missing {:?}", name))?;
+            line.strip_prefix(name)
+                .map(|value| value.trim().to_string())
+                .ok_or(format!("expected {:?}, got {:?}", name, line))
+        };
+        let seed = field("seed")?.parse().map_err(|_| "bad seed".to_string())?;
+        let status = match field("status")?.as_str() {
+            "playing" => GameStatus::Playing,
+            "lost" => GameStatus::Lost,
+            other => return Err(format!("unknown status {:?}", other)),
+        };
+        let best = field("best")?
+            .parse()
+            .map_err(|_| "bad best score".to_string())?;
+
+        let mut game = Endless {
+            seed,
+            chunks: HashMap::new(),
+            status,
+            cleared: 0,
+            best,
+        };
+        while let Some(line) = lines.next().filter(|line| !line.trim().is_empty()) {
+            let position = line
+                .strip_prefix("chunk ")
+                .and_then(|rest| rest.split_once(' '))
+                .and_then(|(cx, cy)| Some((cx.parse().ok()?, cy.parse().ok()?)))
+                .ok_or(format!("expected a chunk, got {:?}", line))?;
+            let chunk = game.chunk(position);
+            for row in 0..CHUNK_SIZE as usize {
+                let line = lines
+                    .next()
+                    .ok_or(format!("chunk {:?} is missing row {}", position, row))?;
+                if line.chars().count() != CHUNK_SIZE as usize {
+                    return Err(format!(
+                        "chunk {:?} row {} has the wrong length",
+                        position, row
+                    ));
+                }
+                for (column, c) in line.chars().enumerate() {
+                    chunk.states[row * CHUNK_SIZE as usize + column] = match c {
+                        'h' => CellState::Hidden,
+                        'r' => CellState::Revealed,
+                        'f' => CellState::Flagged,
+                        _ => return Err(format!("bad cell {:?} in chunk {:?}", c, position)),
+                    };
+                }
+            }
+        }
+
+        // Count the numbers and the score again from the layout.
+        let opened: Vec<(i64, i64)> = game
+            .chunks
+            .iter()
+            .flat_map(|(&(cx, cy), chunk)| {
+                chunk
+                    .states
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, state)| **state == CellState::Revealed && !chunk.mines[*i])
+                    .map(move |(i, _)| {
+                        let i = i as i64;
+                        (
+                            cx * CHUNK_SIZE + i % CHUNK_SIZE,
+                            cy * CHUNK_SIZE + i / CHUNK_SIZE,
+                        )
+                    })
+            })
+            .collect();
+        for (x, y) in opened {
+            let number = neighbors(x, y)
+                .filter(|&(nx, ny)| game.is_mine(nx, ny))
+                .count() as u8;
+            let (chunk, i) = locate(x, y);
+            game.chunk(chunk).numbers[i] = number;
+            game.cleared += 1;
+        }
+        game.best = game.best.max(game.cleared);
+        Ok(game)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn worlds_repeat_from_their_seed() {
+        let (mut a, mut b) = (Endless::new(42), Endless::new(42));
+        a.reveal(40, -25);
+        b.reveal(40, -25);
+        for y in -30..-20 {
+            for x in 35..45 {
+                assert_eq!(a.cell_state(x, y), b.cell_state(x, y));
+                assert_eq!(a.cell(x, y), b.cell(x, y));
+            }
+        }
+        assert_eq!(a.cleared(), b.cleared());
+    }
+
+    #[test]
+    fn numbers_count_the_flat_neighbours() {
+        let mut game = Endless::new(7);
+        for y in -3..=3 {
+            for x in -3..=3 {
+                if let Some(Cell::Number(n)) = game.cell(x, y) {
+                    let mines = neighbors(x, y).filter(|&(nx, ny)| game.is_mine(nx, ny));
+                    assert_eq!(mines.count(), n as usize);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn saves_round_trip() {
+        let mut game = Endless::new(3);
+        game.toggle_flag(5, 5);
+        game.reveal(-8, 2);
+        let loaded = Endless::decode(&game.encode()).unwrap();
+        assert_eq!(loaded.seed(), game.seed());
+        assert_eq!(loaded.status(), game.status());
+        assert_eq!(loaded.cleared(), game.cleared());
+        assert_eq!(loaded.best(), game.best());
+        for y in -12..12 {
+            for x in -12..12 {
+                assert_eq!(loaded.cell_state(x, y), game.cell_state(x, y));
+            }
+        }
+        assert_eq!(loaded.encode(), game.encode());
+    }
+}
@@ -0,0 +1,65 @@
+//engine.error.rs
+use std::fmt;
+
+/// Why a move on the board could not be played (or ended the game).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameError {
+    HitMine {
+        x: usize,
+        y: usize,
+    },
+    AlreadyRevealed {
+        x: usize,
+        y: usize,
+    },
+    ChordFlagMismatch {
+        x: usize,
+        y: usize,
+        expected: u8,
+        flagged: u8,
+    },
+    OutOfBounds {
+        x: usize,
+        y: usize,
+    },
+    GameFinished,
+    /// The board asks for more mines than fit outside the first opening.
+    TooManyMines {
+        mines: usize,
+        max: usize,
+    },
+}
+
+impl fmt::Display for GameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GameError::HitMine { x, y } => {
+                write!(f, "Game Over! You hit a mine at ({}, {}).", x, y)
+            }
+            GameError::AlreadyRevealed { x, y } => {
+                write!(f, "Cell ({}, {}) is already revealed", x, y)
+            }
+            GameError::ChordFlagMismatch {
+                x,
+                y,
+                expected,
+                flagged,
+            } => write!(
+                f,
+                "Cell ({}, {}) needs {} flags around it but has {}",
+                x, y, expected, flagged
+            ),
+            GameError::OutOfBounds { x, y } => {
+                write!(f, "Cell ({}, {}) is outside the board", x, y)
+            }
+            GameError::GameFinished => write!(f, "The game is already finished"),
+            GameError::TooManyMines { mines, max } => write!(
+                f,
+                "{} mines do not fit on this board; it holds at most {}",
+                mines, max
+            ),
+        }
+    }
+}
+
+impl std::error::Error for GameError {}
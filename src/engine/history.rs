@@ -0,0 +1,142 @@
+//engine.history.rs
+use super::{Board, CellState, GameError};
+
+/// One flag or question-mark change.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mark {
+    pub x: usize,
+    pub y: usize,
+    pub before: CellState,
+    pub after: CellState,
+    /// Flags stacked on the cell before and after.
+    pub flags_before: u8,
+    pub flags_after: u8,
+}
+
+impl Mark {
+    /// The same change the other way round.
+    fn reversed(self) -> Self {
+        Mark {
+            before: self.after,
+            after: self.before,
+            flags_before: self.flags_after,
+            flags_after: self.flags_before,
+            ..self
+        }
+    }
+}
+
+/// Puts the cell back to `state` with `flags` flags on it.
+fn restore(board: &mut Board, x: usize, y: usize, state: &CellState, flags: u8) -> bool {
+    match state {
+        CellState::Flagged => board.set_flags(x, y, flags).is_ok(),
+        _ => board.set_mark(x, y, state.clone()).is_ok(),
+    }
+}
+
+/// Undo and redo stacks for marks. Reveals are not recorded; marks on
+/// cells that have been opened since are skipped.
+#[derive(Debug, Clone, Default)]
+pub struct History {
+    done: Vec<Mark>,
+    undone: Vec<Mark>,
+}
+
+impl History {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Toggles the cell and remembers the change.
+    pub fn toggle(&mut self, board: &mut Board, x: usize, y: usize) -> Result<Mark, GameError> {
+        if !board.contains(x, y) {
+            return Err(GameError::OutOfBounds { x, y });
+        }
+        let mark = Mark {
+            x,
+            y,
+            before: board.cell_state(x, y).clone(),
+            after: board.next_mark(x, y),
+            flags_before: board.flags_at(x, y),
+            flags_after: board.next_flags(x, y),
+        };
+        board.toggle_state(x, y)?;
+        self.done.push(mark.clone());
+        self.undone.clear();
+        Ok(mark)
+    }
+
+    /// Reverts the latest mark still on the board. The returned mark
+    /// describes the change just made, so `after` is the restored state.
+    pub fn undo(&mut self, board: &mut Board) -> Option<Mark> {
+        while let Some(mark) = self.done.pop() {
+            if restore(board, mark.x, mark.y, &mark.before, mark.flags_before) {
+                self.undone.push(mark.clone());
+                return Some(mark.reversed());
+            }
+        }
+        None
+    }
+
+    pub fn redo(&mut self, board: &mut Board) -> Option<Mark> {
+        while let Some(mark) = self.undone.pop() {
+            if restore(board, mark.x, mark.y, &mark.after, mark.flags_after) {
+                self.done.push(mark.clone());
+                return Some(mark);
+            }
+        }
+        None
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.done.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.undone.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn undo_and_redo_walk_the_marks() {
+        let mut board = Board::from_layout(4, 4, &[(0, 0), (3, 3)], 1);
+        let mut history = History::new();
+        history.toggle(&mut board, 0, 0).unwrap();
+        history.toggle(&mut board, 3, 3).unwrap();
+
+        let undone = history.undo(&mut board).unwrap();
+        assert_eq!((undone.x, undone.y), (3, 3));
+        assert_eq!(undone.after, CellState::Hidden);
+        assert_eq!(board.cell_state(3, 3), &CellState::Hidden);
+        assert!(history.can_redo());
+        history.redo(&mut board).unwrap();
+        assert_eq!(board.cell_state(3, 3), &CellState::Flagged);
+
+        history.toggle(&mut board, 3, 3).unwrap();
+        assert!(!history.can_redo());
+        assert!(matches!(
+            history.toggle(&mut board, 4, 0),
+            Err(GameError::OutOfBounds { x: 4, y: 0 })
+        ));
+    }
+
+    #[test]
+    fn undo_skips_cells_opened_since() {
+        let mut board = Board::from_layout(4, 4, &[(0, 0), (3, 3)], 1);
+        let mut history = History::new();
+        history.toggle(&mut board, 0, 0).unwrap();
+        history.toggle(&mut board, 2, 0).unwrap();
+        history.toggle(&mut board, 2, 0).unwrap();
+        history.toggle(&mut board, 2, 0).unwrap();
+        board.reveal(2, 0).unwrap();
+
+        let undone = history.undo(&mut board).unwrap();
+        assert_eq!((undone.x, undone.y), (0, 0));
+        assert!(history.undo(&mut board).is_none());
+        assert!(!history.can_undo());
+    }
+}
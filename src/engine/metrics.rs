@@ -0,0 +1,98 @@
+//engine.metrics.rs
+//! Speed and efficiency measures used by competitive players.
+
+use super::Board;
+use std::time::Duration;
+
+/// Clicks made during a game, by kind.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Clicks {
+    /// Reveals of hidden cells.
+    pub left: u32,
+    /// Flag and question mark toggles.
+    pub right: u32,
+    /// Chords on revealed numbers.
+    pub chord: u32,
+}
+
+impl Clicks {
+    pub fn total(&self) -> u32 {
+        self.left + self.right + self.chord
+    }
+}
+
+/// How well a game was played, from its 3BV, time and clicks.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Metrics {
+    pub bbbv: u32,
+    pub seconds: f32,
+    pub clicks: Clicks,
+}
+
+impl Metrics {
+    pub fn of(board: &Board, elapsed: Duration) -> Self {
+        Self {
+            bbbv: board.bbbv(),
+            seconds: elapsed.as_secs_f32(),
+            clicks: board.clicks(),
+        }
+    }
+
+    /// 3BV cleared per second.
+    pub fn bbbv_per_second(&self) -> Option<f32> {
+        (self.seconds > 0.0).then(|| self.bbbv as f32 / self.seconds)
+    }
+
+    /// Index of speed, `ln(3BV) / ln(time)`. Higher is better; undefined
+    /// for games of a second or less.
+    pub fn ios(&self) -> Option<f32> {
+        (self.seconds > 1.0 && self.bbbv > 0).then(|| (self.bbbv as f32).ln() / self.seconds.ln())
+    }
+
+    /// Rapport qualité prix, `time / 3BV/s`. Lower is better.
+    pub fn rqp(&self) -> Option<f32> {
+        (self.bbbv > 0).then(|| self.seconds * self.seconds / self.bbbv as f32)
+    }
+
+    /// 3BV per click; 1.0 means no click was wasted.
+    pub fn efficiency(&self) -> Option<f32> {
+        let clicks = self.clicks.total();
+        (clicks > 0).then(|| self.bbbv as f32 / clicks as f32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn measures_follow_their_formulas() {
+        let metrics = Metrics {
+            bbbv: 40,
+            seconds: 20.0,
+            clicks: Clicks {
+                left: 30,
+                right: 8,
+                chord: 2,
+            },
+        };
+        assert_eq!(metrics.bbbv_per_second(), Some(2.0));
+        assert_eq!(metrics.rqp(), Some(10.0));
+        assert_eq!(metrics.efficiency(), Some(1.0));
+        let ios = metrics.ios().unwrap();
+        assert!((ios - 40f32.ln() / 20f32.ln()).abs() < 1e-6);
+    }
+
+    #[test]
+    fn measures_without_a_base_are_undefined() {
+        let metrics = Metrics {
+            bbbv: 0,
+            seconds: 0.0,
+            clicks: Clicks::default(),
+        };
+        assert_eq!(metrics.bbbv_per_second(), None);
+        assert_eq!(metrics.ios(), None);
+        assert_eq!(metrics.rqp(), None);
+        assert_eq!(metrics.efficiency(), None);
+    }
+}
@@ -0,0 +1,839 @@
+//engine.mod.rs
+#![deny(clippy::all)]
+//! Headless Minesweeper rules. Nothing in here knows about egui, eframe or
+//! the score database, so the GUI, a CLI, bots and tests can all drive the
+//! same `Board`.
+
+mod daily;
+mod difficulty;
+mod endless;
+mod error;
+mod history;
+mod metrics;
+pub mod position;
+pub mod replay;
+mod save;
+pub mod solver;
+mod topology;
+
+pub use daily::Day;
+pub use difficulty::Difficulty;
+pub use endless::Endless;
+pub use error::GameError;
+pub use history::{History, Mark};
+pub use metrics::{Clicks, Metrics};
+pub use save::SavedGame;
+pub use topology::{Grid, Neighborhood, Topology};
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+
+/// What a cell holds. `Mine` covers any number of mines; see
+/// `Board::mines_at` for how many.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Cell {
+    Empty,
+    Mine,
+    /// Mines in the neighbouring cells, counting every mine of a stack.
+    Number(u8),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CellState {
+    Hidden,
+    Revealed,
+    Flagged,
+    Questioned,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameStatus {
+    /// No cell has been opened yet, mines are placed on the first move.
+    Ready,
+    Playing,
+    Won,
+    Lost,
+}
+
+/// How mines are laid out when the first cell is opened.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Generation {
+    Random,
+    /// Only accept layouts the built-in solver clears from the first click
+    /// without guessing. Gives up after `budget` and keeps a random layout.
+    NoGuess {
+        budget: Duration,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct Board {
+    width: usize,
+    height: usize,
+    mine_count: usize,
+    cells: Vec<Vec<Cell>>,
+    /// Mines in each cell, at most `mines_per_cell`.
+    mines: Vec<Vec<u8>>,
+    cell_states: Vec<Vec<CellState>>,
+    initialized: bool,
+    revealed: Vec<Vec<bool>>,
+    /// Flags stacked on each cell; non-zero exactly when it is `Flagged`.
+    flags: Vec<Vec<u8>>,
+    mines_per_cell: u8,
+    status: GameStatus,
+    question_marks: bool,
+    seed: u64,
+    generation: Generation,
+    no_guess: bool,
+    topology: Topology,
+    clicks: Clicks,
+    /// Worked out once the mines are placed.
+    bbbv: u32,
+    daily: Option<Day>,
+}
+
+impl Board {
+    /// Most mines one cell can hold, so a stack always reads as one digit.
+    pub const MAX_MINES_PER_CELL: u8 = 9;
+
+    /// A board with a fresh random seed.
+    pub fn new(width: usize, height: usize, mine_count: usize) -> Self {
+        Self::with_seed(width, height, mine_count, rand::random())
+    }
+
+    /// A board whose layout is fully determined by `seed` and the first
+    /// cell opened (for a given version of the `rand` crate).
+    pub fn with_seed(width: usize, height: usize, mine_count: usize, seed: u64) -> Self {
+        let cells = vec![vec![Cell::Empty; width]; height];
+        let mines = vec![vec![0; width]; height];
+        let cell_states = vec![vec![CellState::Hidden; width]; height];
+        let revealed = vec![vec![false; width]; height];
+        let flags = vec![vec![0; width]; height];
+        Self {
+            width,
+            height,
+            mine_count,
+            cells,
+            mines,
+            cell_states,
+            initialized: false,
+            revealed,
+            flags,
+            mines_per_cell: 1,
+            status: GameStatus::Ready,
+            question_marks: true,
+            seed,
+            generation: Generation::Random,
+            no_guess: false,
+            topology: Topology::FLAT,
+            clicks: Clicks::default(),
+            bbbv: 0,
+            daily: None,
+        }
+    }
+
+    /// A board with mines at exactly `mines`, for replaying or importing a
+    /// game. Duplicate and out-of-range positions are ignored.
+    pub fn from_layout(width: usize, height: usize, mines: &[(usize, usize)], seed: u64) -> Self {
+        Self::from_stacked_layout(width, height, mines, 1, seed)
+    }
+
+    /// Like `from_layout`, but a position listed several times holds that
+    /// many mines, up to `mines_per_cell`.
+    pub fn from_stacked_layout(
+        width: usize,
+        height: usize,
+        mines: &[(usize, usize)],
+        mines_per_cell: u8,
+        seed: u64,
+    ) -> Self {
+        let mut board = Self::with_seed(width, height, 0, seed);
+        board.mines_per_cell = mines_per_cell.clamp(1, Self::MAX_MINES_PER_CELL);
+        for &(x, y) in mines {
+            if board.contains(x, y) && board.mines[y][x] < board.mines_per_cell {
+                board.mines[y][x] += 1;
+                board.mine_count += 1;
+            }
+        }
+        board.place_numbers();
+        board.initialized = true;
+        board.status = GameStatus::Playing;
+        board
+    }
+
+    pub fn with_difficulty(difficulty: Difficulty) -> Self {
+        let (width, height, mines) = difficulty.dimensions();
+        Self::new(width, height, mines)
+    }
+
+    /// Whether right-clicking a flag leaves a `?` mark before clearing it.
+    pub fn set_question_marks(&mut self, enabled: bool) {
+        self.question_marks = enabled;
+    }
+
+    pub fn question_marks(&self) -> bool {
+        self.question_marks
+    }
+
+    /// Has no effect once the mines are placed.
+    pub fn set_generation(&mut self, generation: Generation) {
+        self.generation = generation;
+    }
+
+    pub fn generation(&self) -> Generation {
+        self.generation
+    }
+
+    /// How many mines a cell may hold, from 1 (the classic game) up to
+    /// `MAX_MINES_PER_CELL`. Has no effect once the mines are placed.
+    pub fn set_mines_per_cell(&mut self, mines_per_cell: u8) {
+        if !self.initialized {
+            self.mines_per_cell = mines_per_cell.clamp(1, Self::MAX_MINES_PER_CELL);
+        }
+    }
+
+    pub fn mines_per_cell(&self) -> u8 {
+        self.mines_per_cell
+    }
+
+    /// Recounts the numbers if the mines are already placed.
+    pub fn set_topology(&mut self, topology: Topology) {
+        self.topology = topology;
+        if self.initialized {
+            self.place_numbers();
+        }
+    }
+
+    pub fn topology(&self) -> Topology {
+        self.topology
+    }
+
+    /// Whether the layout was checked to be solvable without guessing.
+    pub fn is_no_guess(&self) -> bool {
+        self.no_guess
+    }
+
+    /// The day whose challenge this board is, if any.
+    pub fn daily(&self) -> Option<Day> {
+        self.daily
+    }
+
+    /// Reveals, chords and mark toggles the player has made.
+    pub fn clicks(&self) -> Clicks {
+        self.clicks
+    }
+
+    /// The board's 3BV: the fewest clicks that clear it, one per opening
+    /// plus one per number that does not border an opening. Zero until the
+    /// mines are placed.
+    pub fn bbbv(&self) -> u32 {
+        self.bbbv
+    }
+
+    fn count_bbbv(&self) -> u32 {
+        let mut counted = vec![vec![false; self.width]; self.height];
+        let mut bbbv = 0;
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if counted[y][x] || self.cells[y][x] != Cell::Empty {
+                    continue;
+                }
+                bbbv += 1;
+                counted[y][x] = true;
+                let mut stack = vec![(x, y)];
+                while let Some((cx, cy)) = stack.pop() {
+                    for (nx, ny) in self.neighbors(cx, cy) {
+                        if !counted[ny][nx] {
+                            counted[ny][nx] = true;
+                            if self.cells[ny][nx] == Cell::Empty {
+                                stack.push((nx, ny));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        let isolated = (0..self.height)
+            .flat_map(|y| (0..self.width).map(move |x| (x, y)))
+            .filter(|&(x, y)| !counted[y][x] && matches!(self.cells[y][x], Cell::Number(_)))
+            .count();
+        bbbv + isolated as u32
+    }
+
+    /// The cells touching `(x, y)` under the board's topology.
+    pub fn neighbors(&self, x: usize, y: usize) -> impl Iterator<Item = (usize, usize)> {
+        self.topology.neighbors(self.width, self.height, x, y)
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn difficulty(&self) -> Difficulty {
+        Difficulty::from_dimensions(self.width, self.height, self.mine_count)
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn mine_count(&self) -> usize {
+        self.mine_count
+    }
+
+    pub fn is_initialized(&self) -> bool {
+        self.initialized
+    }
+
+    pub fn status(&self) -> GameStatus {
+        self.status
+    }
+
+    pub fn is_finished(&self) -> bool {
+        matches!(self.status, GameStatus::Won | GameStatus::Lost)
+    }
+
+    pub fn cell(&self, x: usize, y: usize) -> &Cell {
+        &self.cells[y][x]
+    }
+
+    pub fn cell_state(&self, x: usize, y: usize) -> &CellState {
+        &self.cell_states[y][x]
+    }
+
+    pub fn is_revealed(&self, x: usize, y: usize) -> bool {
+        self.revealed[y][x]
+    }
+
+    pub fn is_flagged(&self, x: usize, y: usize) -> bool {
+        self.flags[y][x] > 0
+    }
+
+    /// How many mines the cell holds. Zero until the first move.
+    pub fn mines_at(&self, x: usize, y: usize) -> u8 {
+        self.mines[y][x]
+    }
+
+    /// How many flags are stacked on the cell.
+    pub fn flags_at(&self, x: usize, y: usize) -> u8 {
+        self.flags[y][x]
+    }
+
+    pub fn contains(&self, x: usize, y: usize) -> bool {
+        x < self.width && y < self.height
+    }
+
+    fn check_move(&self, x: usize, y: usize) -> Result<(), GameError> {
+        if self.is_finished() {
+            return Err(GameError::GameFinished);
+        }
+        if !self.contains(x, y) {
+            return Err(GameError::OutOfBounds { x, y });
+        }
+        Ok(())
+    }
+
+    /// Where the mines are, row by row, a cell listed once per mine it
+    /// holds. Empty until the first move.
+    pub fn mine_positions(&self) -> Vec<(usize, usize)> {
+        (0..self.height)
+            .flat_map(|y| (0..self.width).map(move |x| (x, y)))
+            .flat_map(|(x, y)| std::iter::repeat_n((x, y), self.mines[y][x] as usize))
+            .collect()
+    }
+
+    /// Flags on the board, counting every flag of a stack.
+    pub fn flags_count(&self) -> usize {
+        self.flags.iter().flatten().map(|&f| f as usize).sum()
+    }
+
+    /// Hidden cells that are not mines, i.e. what is left to clear.
+    pub fn safe_cells_left(&self) -> usize {
+        (0..self.height)
+            .flat_map(|y| (0..self.width).map(move |x| (x, y)))
+            .filter(|&(x, y)| !self.revealed[y][x] && self.cells[y][x] != Cell::Mine)
+            .count()
+    }
+
+    /// Lays out the mines for a first click at (x, y) without opening
+    /// anything, so a slow no-guess search can run apart from the move
+    /// itself. Does nothing once the mines are placed.
+    pub fn place_mines(&mut self, first_x: usize, first_y: usize) -> Result<(), GameError> {
+        self.check_move(first_x, first_y)?;
+        if !self.initialized {
+            self.initialize(first_x, first_y)?;
+        }
+        Ok(())
+    }
+
+    fn initialize(&mut self, first_x: usize, first_y: usize) -> Result<(), GameError> {
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        match self.generation {
+            // The solver only knows one mine per cell.
+            _ if self.mines_per_cell > 1 => self.initialize_with(first_x, first_y, &mut rng),
+            Generation::Random => self.initialize_with(first_x, first_y, &mut rng),
+            Generation::NoGuess { budget } => {
+                // Candidates come from the seeded RNG in order, so the same
+                // seed finds the same layout unless the budget runs out.
+                let started = Instant::now();
+                let blank = self.clone();
+                loop {
+                    let mut candidate = blank.clone();
+                    candidate.initialize_with(first_x, first_y, &mut rng)?;
+                    let solvable = solver::solves_without_guessing(&candidate, first_x, first_y);
+                    if solvable || started.elapsed() >= budget {
+                        candidate.no_guess = solvable;
+                        *self = candidate;
+                        return Ok(());
+                    }
+                }
+            }
+        }
+    }
+
+    /// Places the mines using `rng` instead of the board's seed, keeping
+    /// the first click and its neighbours clear. Does nothing once the
+    /// mines are placed, and fails if they do not fit around that opening.
+    pub fn initialize_with<R: Rng>(
+        &mut self,
+        first_x: usize,
+        first_y: usize,
+        rng: &mut R,
+    ) -> Result<(), GameError> {
+        if self.initialized {
+            return Ok(());
+        }
+        let mut mines_placed = 0;
+
+        let mut avoid_positions: HashSet<_> = self.neighbors(first_x, first_y).collect();
+        avoid_positions.insert((first_x, first_y));
+
+        // More mines than the free cells can hold would spin the loop
+        // below forever.
+        let free_cells = self.width * self.height - avoid_positions.len();
+        let max = free_cells * self.mines_per_cell as usize;
+        if self.mine_count > max {
+            return Err(GameError::TooManyMines {
+                mines: self.mine_count,
+                max,
+            });
+        }
+
+        while mines_placed < self.mine_count {
+            let x = rng.gen_range(0..self.width);
+            let y = rng.gen_range(0..self.height);
+            if self.mines[y][x] < self.mines_per_cell && !avoid_positions.contains(&(x, y)) {
+                self.mines[y][x] += 1;
+                mines_placed += 1;
+            }
+        }
+
+        self.place_numbers();
+        self.initialized = true;
+        self.status = GameStatus::Playing;
+        Ok(())
+    }
+
+    fn place_numbers(&mut self) {
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if self.mines[y][x] > 0 {
+                    self.cells[y][x] = Cell::Mine;
+                    continue;
+                }
+                let mine_count = self
+                    .neighbors(x, y)
+                    .fold(0u8, |n, (nx, ny)| n.saturating_add(self.mines[ny][nx]));
+                self.cells[y][x] = match mine_count {
+                    0 => Cell::Empty,
+                    n => Cell::Number(n),
+                };
+            }
+        }
+        self.bbbv = self.count_bbbv();
+    }
+
+    /// Cycles a hidden cell through flagged, questioned and back to hidden.
+    /// When cells can hold several mines, flags stack up to that many
+    /// before moving on.
+    pub fn toggle_state(&mut self, x: usize, y: usize) -> Result<(), GameError> {
+        self.check_move(x, y)?;
+        if !self.revealed[y][x] {
+            self.clicks.right += 1;
+        }
+        match self.next_mark(x, y) {
+            CellState::Flagged => self.set_flags(x, y, self.next_flags(x, y)),
+            mark => self.set_mark(x, y, mark),
+        }
+    }
+
+    /// What `toggle_state` would turn the cell into.
+    pub fn next_mark(&self, x: usize, y: usize) -> CellState {
+        match self.cell_states[y][x] {
+            CellState::Hidden => CellState::Flagged,
+            CellState::Flagged if self.flags[y][x] < self.mines_per_cell => CellState::Flagged,
+            CellState::Flagged if self.question_marks => CellState::Questioned,
+            CellState::Flagged | CellState::Questioned => CellState::Hidden,
+            CellState::Revealed => CellState::Revealed,
+        }
+    }
+
+    /// How many flags `toggle_state` would leave on the cell.
+    pub fn next_flags(&self, x: usize, y: usize) -> u8 {
+        match self.next_mark(x, y) {
+            CellState::Flagged => self.flags[y][x] + 1,
+            _ => 0,
+        }
+    }
+
+    /// Puts a flag, a question mark or nothing on a hidden cell, e.g. to
+    /// undo a toggle. `Revealed` is not a mark and leaves the cell alone.
+    pub fn set_mark(&mut self, x: usize, y: usize, mark: CellState) -> Result<(), GameError> {
+        let flags = (mark == CellState::Flagged) as u8;
+        self.mark(x, y, mark, flags)
+    }
+
+    /// Stacks exactly `flags` flags on a hidden cell, at most
+    /// `mines_per_cell`; zero clears it.
+    pub fn set_flags(&mut self, x: usize, y: usize, flags: u8) -> Result<(), GameError> {
+        let flags = flags.min(self.mines_per_cell);
+        match flags {
+            0 => self.mark(x, y, CellState::Hidden, 0),
+            n => self.mark(x, y, CellState::Flagged, n),
+        }
+    }
+
+    fn mark(&mut self, x: usize, y: usize, mark: CellState, flags: u8) -> Result<(), GameError> {
+        self.check_move(x, y)?;
+        if self.revealed[y][x] {
+            return Err(GameError::AlreadyRevealed { x, y });
+        }
+        if mark == CellState::Revealed {
+            return Ok(());
+        }
+        if !self.initialized {
+            self.initialize(x, y)?;
+        }
+        self.flags[y][x] = flags;
+        self.cell_states[y][x] = mark;
+        self.check_win();
+        Ok(())
+    }
+
+    /// Opens a cell, flood-filling empty regions. Revealing an already
+    /// opened number chords it instead.
+    pub fn reveal(&mut self, x: usize, y: usize) -> Result<(), GameError> {
+        self.check_move(x, y)?;
+        if self.revealed[y][x] {
+            self.clicks.chord += 1;
+        } else {
+            self.clicks.left += 1;
+        }
+        self.open(x, y)
+    }
+
+    /// `reveal` without counting a click.
+    fn open(&mut self, x: usize, y: usize) -> Result<(), GameError> {
+        self.check_move(x, y)?;
+        if !self.initialized {
+            self.initialize(x, y)?;
+        }
+        if self.revealed[y][x] {
+            return self.open_around(x, y);
+        }
+
+        let mut stack = vec![(x, y)];
+        while let Some((cx, cy)) = stack.pop() {
+            if self.revealed[cy][cx] {
+                continue;
+            }
+            self.revealed[cy][cx] = true;
+            self.cell_states[cy][cx] = CellState::Revealed;
+
+            match self.cells[cy][cx] {
+                Cell::Mine => {
+                    self.status = GameStatus::Lost;
+                    self.reveal_all_mines();
+                    return Err(GameError::HitMine { x: cx, y: cy });
+                }
+                Cell::Empty => {
+                    stack.extend(
+                        self.neighbors(cx, cy)
+                            .filter(|&(nx, ny)| !self.revealed[ny][nx]),
+                    );
+                }
+                Cell::Number(_) => {}
+            }
+        }
+        self.check_win();
+        Ok(())
+    }
+
+    /// Opens every unflagged neighbour of a revealed number once the number
+    /// of adjacent flags, stacks counted in full, matches it.
+    /// Chording a hidden cell simply opens it.
+    pub fn chord(&mut self, x: usize, y: usize) -> Result<(), GameError> {
+        self.check_move(x, y)?;
+        self.clicks.chord += 1;
+        self.open_around(x, y)
+    }
+
+    /// `chord` without counting a click.
+    fn open_around(&mut self, x: usize, y: usize) -> Result<(), GameError> {
+        if !self.revealed[y][x] {
+            return self.open(x, y);
+        }
+        let num = match self.cells[y][x] {
+            Cell::Number(num) => num,
+            _ => return Err(GameError::AlreadyRevealed { x, y }),
+        };
+        let mut flagged_count = 0;
+        let mut unopened_cells = Vec::new();
+        for (nx, ny) in self.neighbors(x, y) {
+            if self.flags[ny][nx] > 0 {
+                flagged_count += self.flags[ny][nx];
+            } else if !self.revealed[ny][nx] {
+                unopened_cells.push((nx, ny));
+            }
+        }
+
+        if flagged_count == num {
+            for (nx, ny) in unopened_cells {
+                if !self.revealed[ny][nx] {
+                    self.open(nx, ny)?;
+                }
+            }
+            Ok(())
+        } else {
+            Err(GameError::ChordFlagMismatch {
+                x,
+                y,
+                expected: num,
+                flagged: flagged_count,
+            })
+        }
+    }
+
+    /// Won once every safe cell is open and every mine cell carries exactly
+    /// as many flags as it holds mines.
+    fn check_win(&mut self) {
+        let mut correctly_flagged_cells = 0;
+        let mut revealed_cells = 0;
+
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if self.revealed[y][x] {
+                    revealed_cells += 1;
+                } else if self.mines[y][x] > 0 && self.flags[y][x] == self.mines[y][x] {
+                    correctly_flagged_cells += 1;
+                }
+            }
+        }
+
+        if self.initialized && revealed_cells + correctly_flagged_cells == self.width * self.height
+        {
+            self.status = GameStatus::Won;
+            self.reveal_all_cells();
+        }
+    }
+
+    fn reveal_all_mines(&mut self) {
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if self.cells[y][x] == Cell::Mine {
+                    self.revealed[y][x] = true;
+                    self.cell_states[y][x] = CellState::Revealed;
+                }
+            }
+        }
+    }
+
+    fn reveal_all_cells(&mut self) {
+        for y in 0..self.height {
+            for x in 0..self.width {
+                self.revealed[y][x] = true;
+                self.cell_states[y][x] = CellState::Revealed;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// ```text
+    /// * 1 . .
+    /// 1 1 . .
+    /// . . 1 1
+    /// . . 1 *
+    /// ```
+    fn corners() -> Board {
+        Board::from_layout(4, 4, &[(0, 0), (3, 3)], 1)
+    }
+
+    #[test]
+    fn same_seed_same_layout() {
+        let layout = |seed| {
+            let mut board = Board::with_seed(16, 16, 40, seed);
+            board.reveal(8, 8).unwrap();
+            board.mine_positions()
+        };
+        assert_eq!(layout(5), layout(5));
+        assert_ne!(layout(5), layout(6));
+        assert_eq!(layout(5).len(), 40);
+    }
+
+    #[test]
+    fn mines_placed_ahead_match_the_first_click() {
+        let mut clicked = Board::with_seed(16, 16, 40, 5);
+        clicked.reveal(8, 8).unwrap();
+        let mut placed = Board::with_seed(16, 16, 40, 5);
+        placed.place_mines(8, 8).unwrap();
+        assert!(!placed.is_revealed(8, 8));
+        assert_eq!(placed.clicks().total(), 0);
+        assert_eq!(placed.mine_positions(), clicked.mine_positions());
+        placed.reveal(8, 8).unwrap();
+        assert_eq!(placed.cell_states, clicked.cell_states);
+    }
+
+    #[test]
+    fn torus_edges_wrap_around() {
+        let mut board = corners();
+        board.set_topology(Topology::TORUS);
+        assert_eq!(board.neighbors(0, 0).count(), 8);
+        assert!(board.neighbors(0, 0).any(|cell| cell == (3, 3)));
+        // Both corner mines touch the other two corners through the edges.
+        assert_eq!(*board.cell(3, 0), Cell::Number(2));
+        assert_eq!(*board.cell(0, 3), Cell::Number(2));
+        assert_eq!(*board.cell(1, 1), Cell::Number(1));
+    }
+
+    #[test]
+    fn first_click_opens_its_neighbourhood() {
+        for seed in 0..20 {
+            let mut board = Board::with_seed(9, 9, 70, seed);
+            board.reveal(4, 4).unwrap();
+            assert!(board
+                .neighbors(4, 4)
+                .all(|(x, y)| board.mines_at(x, y) == 0));
+            assert_eq!(board.cell(4, 4), &Cell::Empty);
+        }
+    }
+
+    #[test]
+    fn reveal_opens_numbers_alone_and_floods_empty_cells() {
+        let mut board = corners();
+        board.reveal(1, 0).unwrap();
+        assert!(board.is_revealed(1, 0));
+        assert_eq!(board.cell(1, 0), &Cell::Number(1));
+        assert!(!board.is_revealed(2, 0));
+        assert_eq!(board.status(), GameStatus::Playing);
+
+        board.reveal(3, 0).unwrap();
+        assert_eq!(board.safe_cells_left(), 0);
+        // Won once the mines are flagged too.
+        assert_eq!(board.status(), GameStatus::Playing);
+        board.toggle_state(0, 0).unwrap();
+        board.toggle_state(3, 3).unwrap();
+        assert_eq!(board.status(), GameStatus::Won);
+    }
+
+    #[test]
+    fn reveal_errors() {
+        let mut board = corners();
+        assert_eq!(
+            board.reveal(4, 0),
+            Err(GameError::OutOfBounds { x: 4, y: 0 })
+        );
+        assert_eq!(board.reveal(3, 3), Err(GameError::HitMine { x: 3, y: 3 }));
+        assert_eq!(board.status(), GameStatus::Lost);
+        assert!(board.is_revealed(0, 0));
+        assert_eq!(board.reveal(2, 0), Err(GameError::GameFinished));
+        assert_eq!(board.toggle_state(2, 0), Err(GameError::GameFinished));
+    }
+
+    #[test]
+    fn flags_cycle_through_question_marks_when_enabled() {
+        let mut board = corners();
+        board.set_question_marks(false);
+        board.toggle_state(0, 0).unwrap();
+        assert!(board.is_flagged(0, 0));
+        assert_eq!(board.flags_count(), 1);
+        board.toggle_state(0, 0).unwrap();
+        assert_eq!(board.cell_state(0, 0), &CellState::Hidden);
+
+        board.set_question_marks(true);
+        board.toggle_state(0, 0).unwrap();
+        board.toggle_state(0, 0).unwrap();
+        assert_eq!(board.cell_state(0, 0), &CellState::Questioned);
+        board.toggle_state(0, 0).unwrap();
+        assert_eq!(board.cell_state(0, 0), &CellState::Hidden);
+
+        board.reveal(1, 0).unwrap();
+        assert_eq!(
+            board.toggle_state(1, 0),
+            Err(GameError::AlreadyRevealed { x: 1, y: 0 })
+        );
+    }
+
+    #[test]
+    fn chord_opens_around_a_satisfied_number() {
+        let mut board = corners();
+        board.reveal(1, 0).unwrap();
+        assert_eq!(
+            board.chord(1, 0),
+            Err(GameError::ChordFlagMismatch {
+                x: 1,
+                y: 0,
+                expected: 1,
+                flagged: 0
+            })
+        );
+        board.toggle_state(0, 0).unwrap();
+        board.chord(1, 0).unwrap();
+        assert!(board.is_revealed(0, 1) && board.is_revealed(2, 1));
+        assert_eq!(board.safe_cells_left(), 0);
+        assert_eq!(board.clicks().chord, 2);
+    }
+
+    #[test]
+    fn chord_with_a_wrong_flag_hits_the_mine() {
+        let mut board = corners();
+        board.reveal(1, 0).unwrap();
+        board.toggle_state(0, 1).unwrap();
+        assert_eq!(board.chord(1, 0), Err(GameError::HitMine { x: 0, y: 0 }));
+        assert_eq!(board.status(), GameStatus::Lost);
+    }
+
+    #[test]
+    fn bbbv_counts_openings_and_lone_numbers() {
+        assert_eq!(corners().bbbv(), 1);
+        let mut board = Board::from_layout(3, 1, &[(1, 0)], 1);
+        assert_eq!(board.bbbv(), 2);
+        board.reveal(0, 0).unwrap();
+        board.reveal(2, 0).unwrap();
+        assert_eq!(board.clicks().left, 2);
+    }
+
+    #[test]
+    fn stacked_mines_need_stacked_flags() {
+        let mut board = Board::from_stacked_layout(3, 3, &[(0, 0), (0, 0)], 2, 1);
+        assert_eq!(board.cell(1, 1), &Cell::Number(2));
+        board.reveal(1, 1).unwrap();
+        board.set_flags(0, 0, 1).unwrap();
+        assert!(matches!(
+            board.chord(1, 1),
+            Err(GameError::ChordFlagMismatch { flagged: 1, .. })
+        ));
+        board.set_flags(0, 0, 2).unwrap();
+        board.chord(1, 1).unwrap();
+        assert_eq!(board.status(), GameStatus::Won);
+    }
+}
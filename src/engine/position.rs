@@ -0,0 +1,333 @@
+//engine.position.rs
+//! Board interchange formats.
+//!
+//! The text format is an optional
+//! `WIDTHxHEIGHT:MINES [seed=N] [topology=NAME]` header followed by one
+//! line per row, one character per cell. Without a topology the board is
+//! flat.
+//!
+//! | char  | cell                                   |
+//! |-------|----------------------------------------|
+//! | `*`   | hidden mine                            |
+//! | `.`   | hidden safe cell                       |
+//! | `0-8` | revealed cell and its number           |
+//! | `F/f` | flagged mine / flag on a safe cell     |
+//! | `Q/q` | question mark on a mine / safe cell    |
+//! | `X`   | revealed (exploded) mine               |
+//!
+//! A plain `*`/`.` grid with no header, as many puzzle sites share
+//! layouts, is read as a fresh board. MBF, the binary layout format used
+//! by Minesweeper Arbiter and Viennasweeper, is supported as well for flat
+//! boards.
+
+use super::{Board, Cell, CellState, GameStatus, Topology};
+
+/// Whether [`to_text`] can write the board: its mines are placed, one per
+/// cell, and no number is above 8.
+pub fn fits_text(board: &Board) -> bool {
+    board.initialized
+        && board.mines_per_cell == 1
+        && board
+            .cells
+            .iter()
+            .flatten()
+            .all(|cell| !matches!(cell, Cell::Number(n) if *n > 8))
+}
+
+/// The position as text. Boards that have not been played yet have no
+/// layout to write.
+pub fn to_text(board: &Board) -> Result<String, String> {
+    if !board.initialized {
+        return Err("the mines are placed on the first click".to_string());
+    }
+    if !fits_text(board) {
+        return Err("text positions hold one mine per cell".to_string());
+    }
+    let mut out = format!(
+        "{}x{}:{} seed={}",
+        board.width, board.height, board.mine_count, board.seed
+    );
+    if board.topology != Topology::FLAT {
+        out += &format!(" topology={}", board.topology.name());
+    }
+    out.push('\n');
+    for y in 0..board.height {
+        for x in 0..board.width {
+            let mine = board.cells[y][x] == Cell::Mine;
+            out.push(match (&board.cell_states[y][x], mine) {
+                (CellState::Hidden, true) => '*',
+                (CellState::Hidden, false) => '.',
+                (CellState::Flagged, true) => 'F',
+                (CellState::Flagged, false) => 'f',
+                (CellState::Questioned, true) => 'Q',
+                (CellState::Questioned, false) => 'q',
+                (CellState::Revealed, true) => 'X',
+                (CellState::Revealed, false) => match board.cells[y][x] {
+                    Cell::Number(n) => (b'0' + n) as char,
+                    _ => '0',
+                },
+            });
+        }
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+/// Reads a position written by [`to_text`] or a bare layout grid.
+pub fn from_text(text: &str) -> Result<Board, String> {
+    let mut lines = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .peekable();
+
+    let mut header = None;
+    let mut seed = 0;
+    let mut topology = Topology::FLAT;
+    if let Some(first) = lines.peek() {
+        if first.contains(':') {
+            let mut words = first.split_whitespace();
+            header = words.next().map(parse_header).transpose()?;
+            for word in words {
+                match word.split_once('=') {
+                    Some(("seed", value)) => {
+                        seed = value.parse().map_err(|_| format!("bad seed {:?}", value))?;
+                    }
+                    Some(("topology", name)) => {
+                        topology = Topology::from_name(name)
+                            .ok_or(format!("unknown topology {:?}", name))?;
+                    }
+                    _ => return Err(format!("unknown header field {:?}", word)),
+                }
+            }
+            lines.next();
+        }
+    }
+
+    let rows: Vec<Vec<char>> = lines.map(|line| line.chars().collect()).collect();
+    let height = rows.len();
+    let width = rows.first().map_or(0, Vec::len);
+    if width == 0 {
+        return Err("the position has no cells".to_string());
+    }
+    if let Some(y) = rows.iter().position(|row| row.len() != width) {
+        return Err(format!(
+            "row {} has {} cells, expected {}",
+            y + 1,
+            rows[y].len(),
+            width
+        ));
+    }
+
+    let mut mines = Vec::new();
+    let mut states = vec![vec![CellState::Hidden; width]; height];
+    let mut shown = Vec::new();
+    for (y, row) in rows.iter().enumerate() {
+        for (x, &c) in row.iter().enumerate() {
+            states[y][x] = match c {
+                '*' | '.' => CellState::Hidden,
+                'F' | 'f' => CellState::Flagged,
+                'Q' | 'q' => CellState::Questioned,
+                'X' => CellState::Revealed,
+                '0'..='8' => {
+                    shown.push((x, y, c as u8 - b'0'));
+                    CellState::Revealed
+                }
+                _ => return Err(format!("unknown cell {:?} at ({}, {})", c, x, y)),
+            };
+            if matches!(c, '*' | 'F' | 'Q' | 'X') {
+                mines.push((x, y));
+            }
+        }
+    }
+
+    if let Some((w, h, m)) = header {
+        if (w, h) != (width, height) {
+            return Err(format!(
+                "header says {}x{} but the grid is {}x{}",
+                w, h, width, height
+            ));
+        }
+        if m != mines.len() {
+            return Err(format!(
+                "header says {} mines but the grid has {}",
+                m,
+                mines.len()
+            ));
+        }
+    }
+
+    let mut board = Board::from_layout(width, height, &mines, seed);
+    board.set_topology(topology);
+    for (x, y, n) in shown {
+        let actual = match board.cells[y][x] {
+            Cell::Number(actual) => actual,
+            _ => 0,
+        };
+        if actual != n {
+            return Err(format!(
+                "cell ({}, {}) shows {} but has {} neighbouring mines",
+                x, y, n, actual
+            ));
+        }
+    }
+    board.revealed = states
+        .iter()
+        .map(|row| row.iter().map(|s| *s == CellState::Revealed).collect())
+        .collect();
+    board.flags = states
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|s| (*s == CellState::Flagged) as u8)
+                .collect()
+        })
+        .collect();
+    board.cell_states = states;
+    if mines.iter().any(|&(x, y)| board.revealed[y][x]) {
+        board.status = GameStatus::Lost;
+    } else {
+        board.check_win();
+    }
+    Ok(board)
+}
+
+fn parse_header(spec: &str) -> Result<(usize, usize, usize), String> {
+    let bad = || format!("expected a WIDTHxHEIGHT:MINES header, got {:?}", spec);
+    let (size, mines) = spec.split_once(':').ok_or_else(bad)?;
+    let (width, height) = size.split_once('x').ok_or_else(bad)?;
+    Ok((
+        width.parse().map_err(|_| bad())?,
+        height.parse().map_err(|_| bad())?,
+        mines.parse().map_err(|_| bad())?,
+    ))
+}
+
+/// The mine layout as MBF: width, height, a big-endian mine count and one
+/// `x, y` byte pair per mine. Cell states are not part of the format, and
+/// neither is the topology, so only flat boards with one mine per cell fit.
+pub fn to_mbf(board: &Board) -> Result<Vec<u8>, String> {
+    if !board.initialized {
+        return Err("the mines are placed on the first click".to_string());
+    }
+    if board.topology != Topology::FLAT || board.mines_per_cell > 1 {
+        return Err("MBF only holds flat boards with one mine per cell".to_string());
+    }
+    let mines = board.mine_positions();
+    if board.width > 255 || board.height > 255 || mines.len() > u16::MAX as usize {
+        return Err("MBF only holds boards up to 255x255".to_string());
+    }
+    let mut out = vec![board.width as u8, board.height as u8];
+    out.extend((mines.len() as u16).to_be_bytes());
+    for (x, y) in mines {
+        out.extend([x as u8, y as u8]);
+    }
+    Ok(out)
+}
+
+pub fn from_mbf(bytes: &[u8]) -> Result<Board, String> {
+    let [width, height, hi, lo, rest @ ..] = bytes else {
+        return Err("MBF file is too short".to_string());
+    };
+    let count = u16::from_be_bytes([*hi, *lo]) as usize;
+    if rest.len() != count * 2 {
+        return Err(format!(
+            "MBF header says {} mines but the file holds {}",
+            count,
+            rest.len() / 2
+        ));
+    }
+    let (width, height) = (*width as usize, *height as usize);
+    if width == 0 || height == 0 {
+        return Err("MBF board has no cells".to_string());
+    }
+    let mines: Vec<(usize, usize)> = rest
+        .chunks_exact(2)
+        .map(|pair| (pair[0] as usize, pair[1] as usize))
+        .collect();
+    if let Some(&(x, y)) = mines.iter().find(|&&(x, y)| x >= width || y >= height) {
+        return Err(format!("MBF mine at ({}, {}) is off the board", x, y));
+    }
+    Ok(Board::from_layout(width, height, &mines, 0))
+}
+
+/// Picks the format from the contents: text when it reads as a position,
+/// MBF otherwise.
+pub fn import(bytes: &[u8]) -> Result<Board, String> {
+    match std::str::from_utf8(bytes) {
+        Ok(text)
+            if text
+                .trim_start()
+                .starts_with(|c: char| c.is_ascii_graphic()) =>
+        {
+            from_text(text).or_else(|text_error| from_mbf(bytes).map_err(|_| text_error))
+        }
+        _ => from_mbf(bytes),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn played(topology: Topology) -> Board {
+        let mut board = Board::from_layout(5, 4, &[(0, 0), (4, 1), (2, 3)], 9);
+        board.set_topology(topology);
+        board.reveal(2, 1).unwrap();
+        board.toggle_state(0, 0).unwrap();
+        board
+    }
+
+    #[test]
+    fn text_round_trips_on_every_topology() {
+        for topology in Topology::ALL {
+            let board = played(topology);
+            assert!(fits_text(&board));
+            let text = to_text(&board).unwrap();
+            let read = from_text(&text).unwrap();
+            assert!(read.topology() == topology, "{}", topology.name());
+            assert_eq!(read.cells, board.cells);
+            assert_eq!(read.cell_states, board.cell_states);
+            assert_eq!(to_text(&read).unwrap(), text);
+        }
+    }
+
+    #[test]
+    fn flat_text_has_no_topology_field() {
+        let text = to_text(&played(Topology::FLAT)).unwrap();
+        assert_eq!(text.lines().next(), Some("5x4:3 seed=9"));
+        assert!(from_text("2x1:0 topology=moebius\n..").is_err());
+    }
+
+    #[test]
+    fn unplayed_boards_round_trip_once_clicked() {
+        let mut board = Board::with_seed(9, 9, 10, 4);
+        assert!(!fits_text(&board));
+        assert!(to_text(&board).is_err());
+        assert!(to_mbf(&board).is_err());
+        board.reveal(4, 4).unwrap();
+        let text = to_text(&board).unwrap();
+        let read = from_text(&text).unwrap();
+        assert_eq!(read.mine_positions(), board.mine_positions());
+        assert_eq!(read.cell_states, board.cell_states);
+        assert_eq!(to_text(&read).unwrap(), text);
+    }
+
+    #[test]
+    fn reads_bare_layouts() {
+        let board = from_text("*..\n...\n..*\n").unwrap();
+        assert_eq!(board.mine_positions(), vec![(0, 0), (2, 2)]);
+        assert!(board.topology() == Topology::FLAT);
+        assert!(from_text("*..\n..\n").is_err());
+        assert!(from_text("3x1:2\n*..\n").is_err());
+    }
+
+    #[test]
+    fn mbf_round_trips_flat_boards_only() {
+        let board = played(Topology::FLAT);
+        let read = from_mbf(&to_mbf(&board).unwrap()).unwrap();
+        assert_eq!(read.mine_positions(), board.mine_positions());
+        assert!(to_mbf(&played(Topology::HEX)).is_err());
+        assert!(from_mbf(&[2, 2, 0, 1, 5, 0]).is_err());
+    }
+}
@@ -0,0 +1,335 @@
+//engine.replay.rs
+//! Recorded games. A replay keeps the mine layout next to the seed, so it
+//! plays back exactly even when the layout came from a time-limited
+//! no-guess search.
+
+use super::{Board, CellState, Topology};
+use std::time::Duration;
+
+const MAGIC: &str = "minesweeper replay";
+const VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Reveal,
+    Chord,
+    Flag,
+    /// Leaves this many flags stacked on the cell.
+    Flags(u8),
+    Question,
+    Unmark,
+}
+
+impl Action {
+    fn name(self) -> String {
+        match self {
+            Action::Reveal => "reveal".to_string(),
+            Action::Chord => "chord".to_string(),
+            Action::Flag => "flag".to_string(),
+            Action::Flags(n) => format!("flags{}", n),
+            Action::Question => "question".to_string(),
+            Action::Unmark => "unmark".to_string(),
+        }
+    }
+
+    /// The action that leaves a cell marked with `mark` and `flags` flags.
+    pub fn marking(mark: &CellState, flags: u8) -> Self {
+        match mark {
+            CellState::Flagged if flags > 1 => Action::Flags(flags),
+            CellState::Flagged => Action::Flag,
+            CellState::Questioned => Action::Question,
+            CellState::Hidden | CellState::Revealed => Action::Unmark,
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        if let Some(flags) = name.strip_prefix("flags") {
+            return flags.parse().ok().map(Action::Flags);
+        }
+        [
+            Action::Reveal,
+            Action::Chord,
+            Action::Flag,
+            Action::Question,
+            Action::Unmark,
+        ]
+        .into_iter()
+        .find(|action| action.name() == name)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Event {
+    /// Time since the game started.
+    pub at: Duration,
+    pub action: Action,
+    pub x: usize,
+    pub y: usize,
+    /// Mouse position in cell units, if the mouse was over the board.
+    pub pointer: Option<(f32, f32)>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Replay {
+    pub width: usize,
+    pub height: usize,
+    pub seed: u64,
+    pub question_marks: bool,
+    pub topology: Topology,
+    pub mines_per_cell: u8,
+    /// A cell holding several mines is listed once per mine.
+    pub mines: Vec<(usize, usize)>,
+    pub events: Vec<Event>,
+}
+
+impl Replay {
+    /// Starts a recording of `board`, which should not have been played yet.
+    pub fn new(board: &Board) -> Self {
+        Replay {
+            width: board.width,
+            height: board.height,
+            seed: board.seed,
+            question_marks: board.question_marks,
+            topology: board.topology,
+            mines_per_cell: board.mines_per_cell,
+            mines: Vec::new(),
+            events: Vec::new(),
+        }
+    }
+
+    /// Logs a move made on `board`. Call it after the move so the layout
+    /// chosen by the first click is captured.
+    pub fn record(
+        &mut self,
+        board: &Board,
+        at: Duration,
+        action: Action,
+        x: usize,
+        y: usize,
+        pointer: Option<(f32, f32)>,
+    ) {
+        if self.mines.is_empty() {
+            self.mines = board.mine_positions();
+        }
+        self.events.push(Event {
+            at,
+            action,
+            x,
+            y,
+            pointer,
+        });
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.events.last().map_or(Duration::ZERO, |event| event.at)
+    }
+
+    /// The board before the first move.
+    pub fn start(&self) -> Board {
+        let mut board = Board::from_stacked_layout(
+            self.width,
+            self.height,
+            &self.mines,
+            self.mines_per_cell,
+            self.seed,
+        );
+        board.question_marks = self.question_marks;
+        board.set_topology(self.topology);
+        board
+    }
+
+    /// Re-drives the engine with one recorded move. Moves the engine
+    /// rejected during the game are rejected again, so errors are ignored.
+    pub fn apply(board: &mut Board, event: &Event) {
+        let _ = match event.action {
+            Action::Reveal => board.reveal(event.x, event.y),
+            Action::Chord => board.chord(event.x, event.y),
+            Action::Flag => board.set_mark(event.x, event.y, CellState::Flagged),
+            Action::Flags(n) => board.set_flags(event.x, event.y, n),
+            Action::Question => board.set_mark(event.x, event.y, CellState::Questioned),
+            Action::Unmark => board.set_mark(event.x, event.y, CellState::Hidden),
+        };
+    }
+
+    /// The board as it was `at` into the game, and how many events that took.
+    pub fn board_at(&self, at: Duration) -> (Board, usize) {
+        let mut board = self.start();
+        let applied = self.events.partition_point(|event| event.at <= at);
+        for event in &self.events[..applied] {
+            Self::apply(&mut board, event);
+        }
+        (board, applied)
+    }
+
+    pub fn encode(&self) -> String {
+        let mut out = format!("{} v{}\n", MAGIC, VERSION);
+        out += &format!("size {} {}\n", self.width, self.height);
+        out += &format!("seed {}\n", self.seed);
+        out += &format!("question_marks {}\n", self.question_marks);
+        out += &format!("topology {}\n", self.topology.name());
+        out += &format!("mines_per_cell {}\n", self.mines_per_cell);
+        out += "mines";
+        for (x, y) in &self.mines {
+            out += &format!(" {},{}", x, y);
+        }
+        out += "\nevents\n";
+        for event in &self.events {
+            out += &format!(
+                "{} {} {} {}",
+                event.at.as_millis(),
+                event.action.name(),
+                event.x,
+                event.y
+            );
+            match event.pointer {
+                Some((px, py)) => out += &format!(" {:.2} {:.2}\n", px, py),
+                None => out += " -\n",
+            }
+        }
+        out
+    }
+
+    pub fn decode(text: &str) -> Result<Self, String> {
+        let mut lines = text.lines();
+        let version = lines
+            .next()
+            .and_then(|line| line.strip_prefix(MAGIC))
+            .and_then(|rest| rest.trim().strip_prefix('v'))
+            .and_then(|v| v.parse::<u32>().ok())
+            .ok_or("not a minesweeper replay")?;
+        if version != VERSION {
+            return Err(format!("unsupported replay version {}", version));
+        }
+
+        let mut field = |name: &str| -> Result<Vec<&str>, String> {
+            let line = lines.next().ok_or(format!("missing {:?}", name))?;
+            let mut words = line.split_whitespace();
+            if words.next() != Some(name) {
+                return Err(format!("expected {:?}, got {:?}", name, line));
+            }
+            Ok(words.collect())
+        };
+        fn number<T: std::str::FromStr>(word: Option<&&str>) -> Result<T, String> {
+            word.and_then(|w| w.parse().ok())
+                .ok_or(format!("bad number {:?}", word))
+        }
+
+        let size = field("size")?;
+        let (width, height) = (number(size.first())?, number(size.get(1))?);
+        let seed = number(field("seed")?.first())?;
+        let question_marks = match field("question_marks")?.first() {
+            Some(&"true") => true,
+            Some(&"false") => false,
+            other => return Err(format!("expected true or false, got {:?}", other)),
+        };
+        let topology = field("topology")?;
+        let topology = topology
+            .first()
+            .and_then(|name| Topology::from_name(name))
+            .ok_or(format!("unknown topology {:?}", topology.join(" ")))?;
+        let mines_per_cell = number(field("mines_per_cell")?.first())?;
+        let mines = field("mines")?
+            .iter()
+            .map(|pair| {
+                let (x, y) = pair.split_once(',').ok_or(format!("bad mine {:?}", pair))?;
+                Ok((number(Some(&x))?, number(Some(&y))?))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        field("events")?;
+
+        let events = lines
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                let words: Vec<&str> = line.split_whitespace().collect();
+                let action = words
+                    .get(1)
+                    .and_then(|name| Action::from_name(name))
+                    .ok_or(format!("bad event {:?}", line))?;
+                let pointer = match words.get(4) {
+                    Some(&"-") | None => None,
+                    px => Some((number(px)?, number(words.get(5))?)),
+                };
+                Ok(Event {
+                    at: Duration::from_millis(number(words.first())?),
+                    action,
+                    x: number(words.get(2))?,
+                    y: number(words.get(3))?,
+                    pointer,
+                })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        Ok(Replay {
+            width,
+            height,
+            seed,
+            question_marks,
+            topology,
+            mines_per_cell,
+            mines,
+            events,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A short hex game on stacked mines: a reveal, stacked flags, a chord.
+    fn recorded() -> (Replay, Board) {
+        let mut board = Board::with_seed(8, 6, 12, 21);
+        board.set_topology(Topology::HEX);
+        board.set_mines_per_cell(2);
+        let mut replay = Replay::new(&board);
+        let mut at = Duration::ZERO;
+        let mut play = |board: &mut Board, action, x, y| {
+            at += Duration::from_millis(350);
+            let event = Event {
+                at,
+                action,
+                x,
+                y,
+                pointer: Some((x as f32 + 0.5, y as f32 + 0.25)),
+            };
+            Replay::apply(board, &event);
+            replay.record(board, at, action, x, y, event.pointer);
+        };
+        play(&mut board, Action::Reveal, 3, 3);
+        let (mx, my) = board.mine_positions()[0];
+        play(&mut board, Action::Flags(2), mx, my);
+        play(&mut board, Action::Question, 0, 0);
+        play(&mut board, Action::Unmark, 0, 0);
+        play(&mut board, Action::Chord, 3, 3);
+        (replay, board)
+    }
+
+    #[test]
+    fn plays_back_to_the_same_board() {
+        let (replay, board) = recorded();
+        let (played, applied) = replay.board_at(replay.duration());
+        assert_eq!(applied, replay.events.len());
+        assert_eq!(played.cell_states, board.cell_states);
+        assert_eq!(played.flags, board.flags);
+        assert_eq!(played.status(), board.status());
+    }
+
+    #[test]
+    fn round_trips() {
+        let (replay, _) = recorded();
+        let read = Replay::decode(&replay.encode()).unwrap();
+        assert!(read.topology == Topology::HEX);
+        assert_eq!(read.mines_per_cell, 2);
+        assert_eq!(read.mines, replay.mines);
+        assert_eq!(read.events, replay.events);
+        assert_eq!(read.encode(), replay.encode());
+    }
+
+    #[test]
+    fn rejects_other_files() {
+        let text = recorded().0.encode();
+        assert!(Replay::decode(&text.replace("v1\n", "v2\n")).is_err());
+        assert!(Replay::decode(&text.replace("topology hex", "topology cube")).is_err());
+        assert!(Replay::decode("minesweeper save v1\n").is_err());
+    }
+}
@@ -0,0 +1,278 @@
+//engine.save.rs
+//! Plain-text snapshot of a game in progress. The first line names the
+//! format version so older saves can still be read after it changes.
+
+use super::{Board, CellState, Clicks, Day, GameStatus, Generation, Topology};
+use std::time::Duration;
+
+const MAGIC: &str = "minesweeper save";
+const VERSION: u32 = 1;
+
+/// Everything needed to carry on playing where the player left off.
+#[derive(Debug, Clone)]
+pub struct SavedGame {
+    pub board: Board,
+    pub elapsed: Duration,
+    pub cursor: (usize, usize),
+    /// Whether finishing the game may still set a high score.
+    pub ranked: bool,
+}
+
+impl SavedGame {
+    pub fn encode(&self) -> String {
+        let board = &self.board;
+        let mut out = format!("{} v{}\n", MAGIC, VERSION);
+        out += &format!(
+            "size {} {} {}\n",
+            board.width, board.height, board.mine_count
+        );
+        out += &format!("seed {}\n", board.seed);
+        out += &format!(
+            "status {}\n",
+            match board.status {
+                GameStatus::Ready => "ready",
+                GameStatus::Playing => "playing",
+                GameStatus::Won => "won",
+                GameStatus::Lost => "lost",
+            }
+        );
+        out += &match board.generation {
+            Generation::Random => "generation random\n".to_string(),
+            Generation::NoGuess { budget } => {
+                format!("generation no-guess {}\n", budget.as_millis())
+            }
+        };
+        out += &format!("no_guess {}\n", board.no_guess);
+        out += &format!("question_marks {}\n", board.question_marks);
+        out += &format!("elapsed_ms {}\n", self.elapsed.as_millis());
+        out += &format!("cursor {} {}\n", self.cursor.0, self.cursor.1);
+        out += &format!(
+            "clicks {} {} {}\n",
+            board.clicks.left, board.clicks.right, board.clicks.chord
+        );
+
+        out += &match board.daily {
+            Some(day) => format!("daily {}\n", day.0),
+            None => "daily none\n".to_string(),
+        };
+        out += &format!("topology {}\n", board.topology.name());
+        out += &format!("ranked {}\n", self.ranked);
+        out += &format!("mines_per_cell {}\n", board.mines_per_cell);
+
+        // Numbers are counted again on load, so only the mines are kept.
+        out += "mines\n";
+        for row in &board.mines {
+            out.extend(row.iter().map(|&n| (b'0' + n) as char));
+            out.push('\n');
+        }
+        out += "states\n";
+        for (states, flags) in board.cell_states.iter().zip(&board.flags) {
+            out.extend(states.iter().zip(flags).map(|(state, &n)| match state {
+                CellState::Hidden => 'h',
+                CellState::Revealed => 'r',
+                CellState::Flagged if n > 1 => (b'0' + n) as char,
+                CellState::Flagged => 'f',
+                CellState::Questioned => 'q',
+            }));
+            out.push('\n');
+        }
+        out
+    }
+
+    pub fn decode(text: &str) -> Result<Self, String> {
+        let mut lines = text.lines();
+        let version = lines
+            .next()
+            .and_then(|line| line.strip_prefix(MAGIC))
+            .and_then(|rest| rest.trim().strip_prefix('v'))
+            .and_then(|v| v.parse::<u32>().ok())
+            .ok_or("not a minesweeper save file")?;
+        if version != VERSION {
+            return Err(format!("unsupported save version {}", version));
+        }
+
+        let mut field = |name: &str| -> Result<Vec<String>, String> {
+            let line = lines.next().ok_or(format!("missing {:?}", name))?;
+            let mut words = line.split_whitespace();
+            if words.next() != Some(name) {
+                return Err(format!("expected {:?}, got {:?}", name, line));
+            }
+            Ok(words.map(str::to_string).collect())
+        };
+        fn number<T: std::str::FromStr>(words: &[String], i: usize) -> Result<T, String> {
+            words
+                .get(i)
+                .and_then(|w| w.parse().ok())
+                .ok_or(format!("bad number in {:?}", words.join(" ")))
+        }
+        fn flag(words: &[String]) -> Result<bool, String> {
+            match words.first().map(String::as_str) {
+                Some("true") => Ok(true),
+                Some("false") => Ok(false),
+                _ => Err(format!("expected true or false, got {:?}", words.join(" "))),
+            }
+        }
+
+        let size = field("size")?;
+        let (width, height, mine_count) = (number(&size, 0)?, number(&size, 1)?, number(&size, 2)?);
+        let seed = number(&field("seed")?, 0)?;
+        let status = match field("status")?.first().map(String::as_str) {
+            Some("ready") => GameStatus::Ready,
+            Some("playing") => GameStatus::Playing,
+            Some("won") => GameStatus::Won,
+            Some("lost") => GameStatus::Lost,
+            other => return Err(format!("unknown status {:?}", other)),
+        };
+        let generation = field("generation")?;
+        let generation = match generation.first().map(String::as_str) {
+            Some("random") => Generation::Random,
+            Some("no-guess") => Generation::NoGuess {
+                budget: Duration::from_millis(number(&generation, 1)?),
+            },
+            other => return Err(format!("unknown generation {:?}", other)),
+        };
+        let no_guess = flag(&field("no_guess")?)?;
+        let question_marks = flag(&field("question_marks")?)?;
+        let elapsed = Duration::from_millis(number(&field("elapsed_ms")?, 0)?);
+        let cursor = field("cursor")?;
+        let cursor = (number(&cursor, 0)?, number(&cursor, 1)?);
+        let clicks = field("clicks")?;
+        let clicks = Clicks {
+            left: number(&clicks, 0)?,
+            right: number(&clicks, 1)?,
+            chord: number(&clicks, 2)?,
+        };
+        let daily = field("daily")?;
+        let daily = match daily.first().map(String::as_str) {
+            Some("none") => None,
+            _ => Some(Day(number(&daily, 0)?)),
+        };
+        let topology = field("topology")?;
+        let topology = topology
+            .first()
+            .and_then(|name| Topology::from_name(name))
+            .ok_or(format!("unknown topology {:?}", topology.join(" ")))?;
+        let ranked = flag(&field("ranked")?)?;
+        let mines_per_cell = number(&field("mines_per_cell")?, 0)?;
+
+        field("mines")?;
+        let mines = grid(&mut lines, width, height, |c| {
+            c.to_digit(10)
+                .map(|n| n as u8)
+                .filter(|&n| n <= mines_per_cell)
+        })?;
+        if lines.next() != Some("states") {
+            return Err("missing \"states\"".to_string());
+        }
+        let marks = grid(&mut lines, width, height, |c| match c {
+            'h' => Some((CellState::Hidden, 0)),
+            'r' => Some((CellState::Revealed, 0)),
+            'f' => Some((CellState::Flagged, 1)),
+            'q' => Some((CellState::Questioned, 0)),
+            '2'..='9' => Some((CellState::Flagged, c as u8 - b'0')),
+            _ => None,
+        })?;
+        let cell_states = map_grid(&marks, |(state, _)| state.clone());
+
+        if cursor.0 >= width || cursor.1 >= height {
+            return Err(format!("cursor {:?} is off the board", cursor));
+        }
+        let mut board = Board::with_seed(width, height, mine_count, seed);
+        board.revealed = map_grid(&cell_states, |s| *s == CellState::Revealed);
+        board.flags = map_grid(&marks, |&(_, n)| n);
+        board.mines = mines;
+        board.mines_per_cell = mines_per_cell.clamp(1, Board::MAX_MINES_PER_CELL);
+        board.cell_states = cell_states;
+        board.initialized = status != GameStatus::Ready;
+        board.status = status;
+        board.generation = generation;
+        board.no_guess = no_guess;
+        board.question_marks = question_marks;
+        board.clicks = clicks;
+        board.daily = daily;
+        board.topology = topology;
+        if board.initialized {
+            board.place_numbers();
+        }
+
+        Ok(SavedGame {
+            board,
+            elapsed,
+            cursor,
+            ranked,
+        })
+    }
+}
+
+fn grid<'a, T>(
+    lines: &mut impl Iterator<Item = &'a str>,
+    width: usize,
+    height: usize,
+    parse: impl Fn(char) -> Option<T>,
+) -> Result<Vec<Vec<T>>, String> {
+    (0..height)
+        .map(|y| {
+            let line = lines.next().ok_or(format!("missing row {}", y))?;
+            let row = line
+                .chars()
+                .map(&parse)
+                .collect::<Option<Vec<T>>>()
+                .ok_or(format!("bad character in row {}", y))?;
+            if row.len() != width {
+                return Err(format!(
+                    "row {} has {} cells, expected {}",
+                    y,
+                    row.len(),
+                    width
+                ));
+            }
+            Ok(row)
+        })
+        .collect()
+}
+
+fn map_grid<T, U>(grid: &[Vec<T>], f: impl Fn(&T) -> U) -> Vec<Vec<U>> {
+    grid.iter()
+        .map(|row| row.iter().map(&f).collect())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn game(ranked: bool) -> SavedGame {
+        let mut board = Board::from_layout(4, 3, &[(0, 0), (3, 2)], 7);
+        board.reveal(2, 0).unwrap();
+        board.toggle_state(0, 0).unwrap();
+        SavedGame {
+            board,
+            elapsed: Duration::from_millis(1234),
+            cursor: (2, 1),
+            ranked,
+        }
+    }
+
+    #[test]
+    fn round_trips() {
+        for ranked in [true, false] {
+            let saved = game(ranked);
+            let loaded = SavedGame::decode(&saved.encode()).unwrap();
+            assert_eq!(loaded.ranked, ranked);
+            assert_eq!(loaded.elapsed, saved.elapsed);
+            assert_eq!(loaded.cursor, saved.cursor);
+            assert_eq!(loaded.board.mine_positions(), saved.board.mine_positions());
+            assert_eq!(loaded.board.status(), saved.board.status());
+            assert_eq!(loaded.board.cell_states, saved.board.cell_states);
+            assert_eq!(loaded.encode(), saved.encode());
+        }
+    }
+
+    #[test]
+    fn rejects_other_files() {
+        let text = game(true).encode();
+        assert!(SavedGame::decode(&text.replace(&format!("v{}\n", VERSION), "v2\n")).is_err());
+        assert!(SavedGame::decode(&text.replace("cursor 2 1", "cursor 4 1")).is_err());
+        assert!(SavedGame::decode("high scores\n").is_err());
+    }
+}
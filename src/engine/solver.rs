@@ -0,0 +1,886 @@
+//engine.solver.rs
+//! Logical deductions from what the player can see: revealed numbers and
+//! flags. The solver never looks at where the mines actually are, so its
+//! answers are exactly what a careful player could work out.
+
+use super::{Board, Cell, CellState, Topology};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// Search steps allowed per frontier component before enumeration gives up
+/// on it. Keeps hints and no-guess generation responsive on huge frontiers.
+const ENUMERATION_BUDGET: usize = 200_000;
+
+/// What the player knows about one cell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Knowledge {
+    Hidden,
+    /// Flagged by the player, or an exploded mine after a loss. Treated as
+    /// a known mine.
+    Flagged,
+    /// An opened cell and its number, `0` for an empty cell.
+    Revealed(u8),
+}
+
+#[derive(Debug, Clone)]
+pub struct PlayerView {
+    width: usize,
+    height: usize,
+    mine_count: usize,
+    topology: Topology,
+    cells: Vec<Knowledge>,
+}
+
+impl PlayerView {
+    pub fn from_board(board: &Board) -> Self {
+        let mut cells = Vec::with_capacity(board.width * board.height);
+        for y in 0..board.height {
+            for x in 0..board.width {
+                cells.push(match board.cell_states[y][x] {
+                    CellState::Revealed => match board.cells[y][x] {
+                        Cell::Empty => Knowledge::Revealed(0),
+                        Cell::Number(n) => Knowledge::Revealed(n),
+                        Cell::Mine => Knowledge::Flagged,
+                    },
+                    CellState::Flagged => Knowledge::Flagged,
+                    CellState::Hidden | CellState::Questioned => Knowledge::Hidden,
+                });
+            }
+        }
+        Self {
+            width: board.width,
+            height: board.height,
+            mine_count: board.mine_count,
+            topology: board.topology,
+            cells,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Knowledge {
+        self.cells[y * self.width + x]
+    }
+
+    /// Mines not yet accounted for by flags.
+    pub fn mines_left(&self) -> usize {
+        let flagged = self
+            .cells
+            .iter()
+            .filter(|&&k| k == Knowledge::Flagged)
+            .count();
+        self.mine_count.saturating_sub(flagged)
+    }
+
+    fn neighbors(&self, x: usize, y: usize) -> Vec<(usize, usize)> {
+        self.topology
+            .neighbors(self.width, self.height, x, y)
+            .collect()
+    }
+
+    /// One constraint per revealed number that still touches hidden cells.
+    fn constraints(&self) -> Vec<Constraint> {
+        let mut constraints = Vec::new();
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let Knowledge::Revealed(number) = self.get(x, y) else {
+                    continue;
+                };
+                let mut hidden = Vec::new();
+                let mut flagged = 0;
+                for (nx, ny) in self.neighbors(x, y) {
+                    match self.get(nx, ny) {
+                        Knowledge::Hidden => hidden.push((nx, ny)),
+                        Knowledge::Flagged => flagged += 1,
+                        Knowledge::Revealed(_) => {}
+                    }
+                }
+                if !hidden.is_empty() {
+                    constraints.push(Constraint {
+                        origin: (x, y),
+                        cells: hidden,
+                        mines: (number as usize).saturating_sub(flagged),
+                    });
+                }
+            }
+        }
+        constraints
+    }
+}
+
+/// "Exactly `mines` of `cells` are mines", read off the number at `origin`.
+#[derive(Debug, Clone)]
+struct Constraint {
+    origin: (usize, usize),
+    cells: Vec<(usize, usize)>,
+    mines: usize,
+}
+
+/// How a deduction was reached, so a hint can explain itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reason {
+    /// The number at `at` is satisfied by its flags, or needs every hidden
+    /// neighbour to be a mine.
+    Single { at: (usize, usize) },
+    /// The hidden neighbours of `inner` are all neighbours of `outer` too,
+    /// so the difference between the two numbers falls on the rest.
+    Subset {
+        outer: (usize, usize),
+        inner: (usize, usize),
+    },
+    /// Every mine is flagged, or every hidden cell must be a mine.
+    MineCount,
+    /// Every arrangement of mines that fits the numbers around `at` (and
+    /// the mines left) agrees on this cell.
+    Enumeration { at: (usize, usize) },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Deduction {
+    pub x: usize,
+    pub y: usize,
+    pub mine: bool,
+    pub reason: Reason,
+}
+
+type Rule = fn(&PlayerView, &[Constraint]) -> Vec<Deduction>;
+
+/// Everything the simplest applicable rule proves about hidden cells.
+/// Returns nothing when the position needs a guess (or is finished).
+pub fn deduce(view: &PlayerView) -> Vec<Deduction> {
+    let constraints = view.constraints();
+    let rules: [Rule; 4] = [single_rule, subset_rule, mine_count_rule, enumeration_rule];
+    for rule in rules {
+        let found = dedup(rule(view, &constraints));
+        if !found.is_empty() {
+            return found;
+        }
+    }
+    Vec::new()
+}
+
+fn single_rule(_view: &PlayerView, constraints: &[Constraint]) -> Vec<Deduction> {
+    let mut found = Vec::new();
+    for c in constraints {
+        let mine = if c.mines == 0 {
+            false
+        } else if c.mines == c.cells.len() {
+            true
+        } else {
+            continue;
+        };
+        found.extend(c.cells.iter().map(|&(x, y)| Deduction {
+            x,
+            y,
+            mine,
+            reason: Reason::Single { at: c.origin },
+        }));
+    }
+    found
+}
+
+fn subset_rule(_view: &PlayerView, constraints: &[Constraint]) -> Vec<Deduction> {
+    // Which constraints each hidden cell appears in.
+    let mut containing: HashMap<(usize, usize), Vec<&Constraint>> = HashMap::new();
+    for c in constraints {
+        for &cell in &c.cells {
+            containing.entry(cell).or_default().push(c);
+        }
+    }
+    let mut found = Vec::new();
+    for inner in constraints {
+        let inner_cells: HashSet<_> = inner.cells.iter().collect();
+        // Only constraints sharing inner's cells can hold them all.
+        let Some(first) = inner.cells.first() else {
+            continue;
+        };
+        for outer in &containing[first] {
+            if outer.origin == inner.origin
+                || outer.cells.len() <= inner.cells.len()
+                || outer.mines < inner.mines
+            {
+                continue;
+            }
+            let outer_cells: HashSet<_> = outer.cells.iter().collect();
+            if !inner_cells.is_subset(&outer_cells) {
+                continue;
+            }
+            let rest: Vec<_> = outer
+                .cells
+                .iter()
+                .filter(|cell| !inner_cells.contains(cell))
+                .collect();
+            let rest_mines = outer.mines - inner.mines;
+            let mine = if rest_mines == 0 {
+                false
+            } else if rest_mines == rest.len() {
+                true
+            } else {
+                continue;
+            };
+            found.extend(rest.into_iter().map(|&(x, y)| Deduction {
+                x,
+                y,
+                mine,
+                reason: Reason::Subset {
+                    outer: outer.origin,
+                    inner: inner.origin,
+                },
+            }));
+        }
+    }
+    found
+}
+
+fn mine_count_rule(view: &PlayerView, _constraints: &[Constraint]) -> Vec<Deduction> {
+    let hidden: Vec<_> = (0..view.height)
+        .flat_map(|y| (0..view.width).map(move |x| (x, y)))
+        .filter(|&(x, y)| view.get(x, y) == Knowledge::Hidden)
+        .collect();
+    let mines_left = view.mines_left();
+    let mine = if mines_left == 0 {
+        false
+    } else if mines_left == hidden.len() {
+        true
+    } else {
+        return Vec::new();
+    };
+    hidden
+        .into_iter()
+        .map(|(x, y)| Deduction {
+            x,
+            y,
+            mine,
+            reason: Reason::MineCount,
+        })
+        .collect()
+}
+
+fn enumeration_rule(view: &PlayerView, constraints: &[Constraint]) -> Vec<Deduction> {
+    let mut found = Vec::new();
+    for component in components(constraints) {
+        let Some(tallies) = component.enumerate(view) else {
+            continue;
+        };
+        let total: f64 = tallies.values().map(|t| t.count).sum();
+        if total == 0.0 {
+            continue;
+        }
+        for (i, &(x, y)) in component.cells.iter().enumerate() {
+            let as_mine: f64 = tallies.values().map(|t| t.per_cell[i]).sum();
+            let mine = if as_mine == 0.0 {
+                false
+            } else if as_mine == total {
+                true
+            } else {
+                continue;
+            };
+            found.push(Deduction {
+                x,
+                y,
+                mine,
+                reason: Reason::Enumeration {
+                    at: component.origin_of(i),
+                },
+            });
+        }
+    }
+    found
+}
+
+/// Hidden frontier cells tied together by the numbers that touch them.
+/// Components can be solved independently of each other.
+#[derive(Debug)]
+struct Component {
+    cells: Vec<(usize, usize)>,
+    /// Indices into `cells` and the mines they must hold.
+    constraints: Vec<(Vec<usize>, usize)>,
+    origins: Vec<(usize, usize)>,
+}
+
+/// Solutions of a component that use a given number of mines.
+#[derive(Debug, Clone)]
+struct Tally {
+    count: f64,
+    /// How many of those solutions put a mine on each cell.
+    per_cell: Vec<f64>,
+}
+
+fn components(constraints: &[Constraint]) -> Vec<Component> {
+    // Union-find over constraints that share a hidden cell.
+    let mut parent: Vec<usize> = (0..constraints.len()).collect();
+    fn find(parent: &mut [usize], i: usize) -> usize {
+        let mut root = i;
+        while parent[root] != root {
+            root = parent[root];
+        }
+        parent[i] = root;
+        root
+    }
+    let mut owner: HashMap<(usize, usize), usize> = HashMap::new();
+    for (i, c) in constraints.iter().enumerate() {
+        for cell in &c.cells {
+            if let Some(&j) = owner.get(cell) {
+                let (a, b) = (find(&mut parent, i), find(&mut parent, j));
+                parent[a] = b;
+            } else {
+                owner.insert(*cell, i);
+            }
+        }
+    }
+
+    let mut groups: BTreeMap<usize, Vec<&Constraint>> = BTreeMap::new();
+    for (i, c) in constraints.iter().enumerate() {
+        let root = find(&mut parent, i);
+        groups.entry(root).or_default().push(c);
+    }
+
+    groups
+        .into_values()
+        .map(|group| {
+            let mut cells = Vec::new();
+            let mut index = HashMap::new();
+            let mut component_constraints = Vec::new();
+            let mut origins = Vec::new();
+            // Cells are numbered constraint by constraint so neighbouring
+            // cells end up close together, which keeps the search shallow.
+            for c in group {
+                let indices = c
+                    .cells
+                    .iter()
+                    .map(|cell| {
+                        *index.entry(*cell).or_insert_with(|| {
+                            cells.push(*cell);
+                            cells.len() - 1
+                        })
+                    })
+                    .collect();
+                component_constraints.push((indices, c.mines));
+                origins.push(c.origin);
+            }
+            Component {
+                cells,
+                constraints: component_constraints,
+                origins,
+            }
+        })
+        .collect()
+}
+
+impl Component {
+    /// The number that first mentions cell `i`.
+    fn origin_of(&self, i: usize) -> (usize, usize) {
+        self.constraints
+            .iter()
+            .position(|(cells, _)| cells.contains(&i))
+            .map(|c| self.origins[c])
+            .unwrap_or(self.origins[0])
+    }
+
+    /// Every assignment of mines to the component's cells that satisfies
+    /// its numbers, grouped by mine count. `None` if the search budget ran
+    /// out. Assignments that could not fit the remaining mines on the rest
+    /// of the board are dropped.
+    fn enumerate(&self, view: &PlayerView) -> Option<BTreeMap<usize, Tally>> {
+        let hidden = view
+            .cells
+            .iter()
+            .filter(|&&k| k == Knowledge::Hidden)
+            .count();
+        let mines_left = view.mines_left();
+        let elsewhere = hidden - self.cells.len();
+
+        let mut search = Search {
+            component: self,
+            cell_constraints: vec![Vec::new(); self.cells.len()],
+            placed: vec![0; self.constraints.len()],
+            open: self.constraints.iter().map(|(c, _)| c.len()).collect(),
+            assignment: vec![false; self.cells.len()],
+            max_mines: mines_left,
+            min_mines: mines_left.saturating_sub(elsewhere),
+            budget: ENUMERATION_BUDGET,
+            tallies: BTreeMap::new(),
+        };
+        for (c, (cells, _)) in self.constraints.iter().enumerate() {
+            for &i in cells {
+                search.cell_constraints[i].push(c);
+            }
+        }
+        if search.run(0, 0) {
+            Some(search.tallies)
+        } else {
+            None
+        }
+    }
+}
+
+struct Search<'a> {
+    component: &'a Component,
+    cell_constraints: Vec<Vec<usize>>,
+    /// Mines placed so far on each constraint's cells.
+    placed: Vec<usize>,
+    /// Cells of each constraint not assigned yet.
+    open: Vec<usize>,
+    assignment: Vec<bool>,
+    max_mines: usize,
+    min_mines: usize,
+    budget: usize,
+    tallies: BTreeMap<usize, Tally>,
+}
+
+impl Search<'_> {
+    /// Returns `false` when the budget is exhausted.
+    fn run(&mut self, i: usize, mines: usize) -> bool {
+        if self.budget == 0 {
+            return false;
+        }
+        self.budget -= 1;
+
+        if i == self.assignment.len() {
+            if mines >= self.min_mines {
+                let n = self.assignment.len();
+                let tally = self.tallies.entry(mines).or_insert_with(|| Tally {
+                    count: 0.0,
+                    per_cell: vec![0.0; n],
+                });
+                tally.count += 1.0;
+                for (j, &is_mine) in self.assignment.iter().enumerate() {
+                    if is_mine {
+                        tally.per_cell[j] += 1.0;
+                    }
+                }
+            }
+            return true;
+        }
+
+        for is_mine in [false, true] {
+            if is_mine && mines == self.max_mines {
+                continue;
+            }
+            self.assign(i, is_mine, true);
+            let consistent = self.cell_constraints[i].iter().all(|&c| {
+                let need = self.component.constraints[c].1;
+                self.placed[c] <= need && self.placed[c] + self.open[c] >= need
+            });
+            let finished = !consistent || self.run(i + 1, mines + is_mine as usize);
+            self.assign(i, is_mine, false);
+            if !finished {
+                return false;
+            }
+        }
+        true
+    }
+
+    fn assign(&mut self, i: usize, is_mine: bool, apply: bool) {
+        self.assignment[i] = is_mine && apply;
+        for &c in &self.cell_constraints[i] {
+            if apply {
+                self.open[c] -= 1;
+                self.placed[c] += is_mine as usize;
+            } else {
+                self.open[c] += 1;
+                self.placed[c] -= is_mine as usize;
+            }
+        }
+    }
+}
+
+/// Chance that each hidden cell is a mine given everything visible.
+#[derive(Debug, Clone)]
+pub struct Probabilities {
+    width: usize,
+    cells: Vec<Option<f64>>,
+    /// `false` when part of the frontier was too large to enumerate and was
+    /// treated as unconstrained instead.
+    pub exact: bool,
+}
+
+impl Probabilities {
+    /// `None` for cells that are not hidden.
+    pub fn get(&self, x: usize, y: usize) -> Option<f64> {
+        self.cells[y * self.width + x]
+    }
+}
+
+/// Exact mine probabilities for every hidden cell. Each frontier component
+/// is enumerated, and its solutions are weighted by the number of ways the
+/// remaining mines can be spread over the unconstrained cells.
+pub fn probabilities(view: &PlayerView) -> Probabilities {
+    let mut exact = true;
+    let mut solved = Vec::new();
+    for component in components(&view.constraints()) {
+        match component.enumerate(view) {
+            Some(tallies) => solved.push((component, tallies)),
+            None => exact = false,
+        }
+    }
+
+    let hidden = view
+        .cells
+        .iter()
+        .filter(|&&k| k == Knowledge::Hidden)
+        .count();
+    let frontier: usize = solved.iter().map(|(c, _)| c.cells.len()).sum();
+    let interior = hidden - frontier;
+    let mines_left = view.mines_left();
+
+    // weight[s]: ways to place the other `mines_left - s` mines on the
+    // interior, scaled down by the largest term to stay within f64.
+    let ln_factorial: Vec<f64> = std::iter::once(0.0)
+        .chain((1..=interior).scan(0.0, |acc, n| {
+            *acc += (n as f64).ln();
+            Some(*acc)
+        }))
+        .collect();
+    let ln_choose =
+        |k: usize| ln_factorial[interior] - ln_factorial[k] - ln_factorial[interior - k];
+    let ln_weights: Vec<Option<f64>> = (0..=mines_left)
+        .map(|s| {
+            Some(mines_left - s)
+                .filter(|&k| k <= interior)
+                .map(ln_choose)
+        })
+        .collect();
+    let max_ln = ln_weights.iter().flatten().fold(f64::MIN, |a, &b| a.max(b));
+    let weight = |s: usize| {
+        ln_weights
+            .get(s)
+            .copied()
+            .flatten()
+            .map_or(0.0, |ln| (ln - max_ln).exp())
+    };
+
+    let distributions: Vec<Vec<f64>> = solved
+        .iter()
+        .map(|(_, tallies)| {
+            let mut dist = vec![0.0; tallies.keys().max().map_or(1, |m| m + 1)];
+            for (&m, tally) in tallies {
+                dist[m] = tally.count;
+            }
+            dist
+        })
+        .collect();
+    let all = convolve(distributions.iter());
+    let total: f64 = all.iter().enumerate().map(|(s, n)| n * weight(s)).sum();
+
+    let mut cells: Vec<Option<f64>> = view
+        .cells
+        .iter()
+        .map(|&k| (k == Knowledge::Hidden).then_some(0.0))
+        .collect();
+    if total == 0.0 {
+        // The flags contradict the numbers; nothing sensible to show.
+        return Probabilities {
+            width: view.width,
+            cells: cells.iter().map(|_| None).collect(),
+            exact: false,
+        };
+    }
+
+    let interior_probability = if interior == 0 {
+        0.0
+    } else {
+        all.iter()
+            .enumerate()
+            .map(|(s, n)| n * weight(s) * mines_left.saturating_sub(s) as f64)
+            .sum::<f64>()
+            / (interior as f64 * total)
+    };
+    for p in cells.iter_mut().flatten() {
+        *p = interior_probability;
+    }
+
+    for (i, (component, tallies)) in solved.iter().enumerate() {
+        let others = convolve(
+            distributions
+                .iter()
+                .enumerate()
+                .filter(|&(j, _)| j != i)
+                .map(|(_, d)| d),
+        );
+        let mut per_cell = vec![0.0; component.cells.len()];
+        for (&m, tally) in tallies {
+            let factor: f64 = others
+                .iter()
+                .enumerate()
+                .map(|(s, n)| n * weight(m + s))
+                .sum();
+            for (p, &as_mine) in per_cell.iter_mut().zip(&tally.per_cell) {
+                *p += as_mine * factor;
+            }
+        }
+        for (&(x, y), p) in component.cells.iter().zip(per_cell) {
+            cells[y * view.width + x] = Some(p / total);
+        }
+    }
+
+    Probabilities {
+        width: view.width,
+        cells,
+        exact,
+    }
+}
+
+/// Distribution of the total mine count over independent components,
+/// each given as solution counts indexed by mine count.
+fn convolve<'a>(distributions: impl Iterator<Item = &'a Vec<f64>>) -> Vec<f64> {
+    distributions.fold(vec![1.0], |acc, dist| {
+        let mut out = vec![0.0; acc.len() + dist.len() - 1];
+        for (a, x) in acc.iter().enumerate() {
+            for (b, y) in dist.iter().enumerate() {
+                out[a + b] += x * y;
+            }
+        }
+        out
+    })
+}
+
+/// Keeps the first deduction for each cell.
+fn dedup(deductions: Vec<Deduction>) -> Vec<Deduction> {
+    let mut seen = HashSet::new();
+    deductions
+        .into_iter()
+        .filter(|d| seen.insert((d.x, d.y)))
+        .collect()
+}
+
+/// One deducible cell for the player, preferring safe cells to mines since
+/// those move the game forward. `None` means the position needs a guess.
+pub fn hint(view: &PlayerView) -> Option<Deduction> {
+    let found = deduce(view);
+    found
+        .iter()
+        .find(|d| !d.mine)
+        .or_else(|| found.first())
+        .copied()
+}
+
+impl Deduction {
+    /// A sentence for the player explaining the deduction.
+    pub fn explain(&self) -> String {
+        let what = if self.mine { "a mine" } else { "safe" };
+        let cell = format!("({}, {})", self.x, self.y);
+        match self.reason {
+            Reason::Single { at: (x, y) } if self.mine => format!(
+                "{} is {}: the number at ({}, {}) needs every one of its hidden neighbours to be a mine.",
+                cell, what, x, y
+            ),
+            Reason::Single { at: (x, y) } => format!(
+                "{} is {}: the number at ({}, {}) already touches all of its mines.",
+                cell, what, x, y
+            ),
+            Reason::Subset {
+                outer: (ox, oy),
+                inner: (ix, iy),
+            } => format!(
+                "{} is {}: the hidden neighbours of ({}, {}) are all neighbours of ({}, {}) too, so the difference between the two numbers decides the rest.",
+                cell, what, ix, iy, ox, oy
+            ),
+            Reason::MineCount if self.mine => format!(
+                "{} is {}: the mines left exactly fill the hidden cells.",
+                cell, what
+            ),
+            Reason::MineCount => format!("{} is {}: every mine is already flagged.", cell, what),
+            Reason::Enumeration { at: (x, y) } => format!(
+                "{} is {}: every arrangement of mines that fits the numbers around ({}, {}) agrees on it.",
+                cell, what, x, y
+            ),
+        }
+    }
+}
+
+/// Plays `board` from the first click using only deductions, opening safe
+/// cells and flagging mines until it is cleared or a guess is needed.
+pub fn solves_without_guessing(board: &Board, first_x: usize, first_y: usize) -> bool {
+    let mut sim = board.clone();
+    sim.set_question_marks(false);
+    if sim.reveal(first_x, first_y).is_err() {
+        return false;
+    }
+    loop {
+        if sim.status() == super::GameStatus::Won || sim.safe_cells_left() == 0 {
+            return true;
+        }
+        let found = deduce(&PlayerView::from_board(&sim));
+        if found.is_empty() {
+            return false;
+        }
+        for d in found {
+            if sim.is_finished() {
+                break;
+            }
+            let result = if d.mine {
+                if sim.is_flagged(d.x, d.y) {
+                    continue;
+                }
+                sim.toggle_state(d.x, d.y)
+            } else {
+                if sim.is_revealed(d.x, d.y) {
+                    continue;
+                }
+                sim.reveal(d.x, d.y)
+            };
+            if result.is_err() {
+                // Only possible if a deduction was wrong.
+                return false;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::Generation;
+    use std::time::Duration;
+
+    /// Plays `board` by deduction alone, checking every deduction against
+    /// the real layout, and returns all of them.
+    fn play_by_deduction(board: &mut Board) -> Vec<Deduction> {
+        let mut all = Vec::new();
+        while !board.is_finished() {
+            let found = deduce(&PlayerView::from_board(board));
+            if found.is_empty() {
+                break;
+            }
+            for d in &found {
+                let mine = board.mines_at(d.x, d.y) > 0;
+                assert_eq!(d.mine, mine, "{:?}", d);
+                if d.mine {
+                    board.set_flags(d.x, d.y, 1).unwrap();
+                } else if !board.is_revealed(d.x, d.y) {
+                    board.reveal(d.x, d.y).unwrap();
+                }
+            }
+            all.extend(found);
+        }
+        all
+    }
+
+    #[test]
+    fn deductions_hold_on_every_topology() {
+        for topology in Topology::ALL {
+            let mut all = Vec::new();
+            for seed in 0..20 {
+                let mut board = Board::with_seed(12, 10, 18, seed);
+                board.set_topology(topology);
+                board.reveal(6, 5).unwrap();
+                all.extend(play_by_deduction(&mut board));
+            }
+            assert!(!all.is_empty(), "{} made no deductions", topology.name());
+        }
+    }
+
+    #[test]
+    fn no_guess_boards_clear_by_deduction() {
+        for seed in 0..5 {
+            let mut board = Board::with_seed(9, 9, 10, seed);
+            board.set_generation(Generation::NoGuess {
+                budget: Duration::from_secs(2),
+            });
+            board.reveal(4, 4).unwrap();
+            assert!(board.is_no_guess());
+            play_by_deduction(&mut board);
+            assert_eq!(board.safe_cells_left(), 0, "seed {}", seed);
+        }
+    }
+
+    /// A position as the player sees it: `#` hidden, `F` flagged and digits
+    /// for opened cells.
+    fn view(mine_count: usize, rows: &[&str]) -> PlayerView {
+        PlayerView {
+            width: rows[0].len(),
+            height: rows.len(),
+            mine_count,
+            topology: Topology::FLAT,
+            cells: rows
+                .iter()
+                .flat_map(|row| row.chars())
+                .map(|c| match c {
+                    '#' => Knowledge::Hidden,
+                    'F' => Knowledge::Flagged,
+                    _ => Knowledge::Revealed(c.to_digit(10).unwrap() as u8),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn enumeration_settles_the_one_two_one() {
+        let view = view(2, &["###", "121"]);
+        let mut found: Vec<_> = enumeration_rule(&view, &view.constraints())
+            .into_iter()
+            .inspect(|d| assert!(matches!(d.reason, Reason::Enumeration { .. })))
+            .map(|d| (d.x, d.y, d.mine))
+            .collect();
+        found.sort();
+        assert_eq!(found, vec![(0, 0, true), (1, 0, false), (2, 0, true)]);
+    }
+
+    #[test]
+    fn hints_prefer_safe_cells_and_explain_themselves() {
+        let hint = hint(&view(2, &["#11F1#"])).unwrap();
+        assert_eq!((hint.x, hint.y, hint.mine), (5, 0, false));
+        assert_eq!(
+            hint.explain(),
+            "(5, 0) is safe: the number at (4, 0) already touches all of its mines."
+        );
+    }
+
+    #[test]
+    fn a_fifty_fifty_has_no_hint() {
+        assert_eq!(hint(&view(1, &["#1#"])), None);
+    }
+
+    #[test]
+    fn probabilities_weigh_solutions_by_the_interior() {
+        // Either (2, 0) is the mine both numbers see, leaving one for the
+        // three cells past (4, 0), or (0, 0) and (4, 0) are mines and those
+        // three are empty. The first can happen three ways.
+        let odds = probabilities(&view(2, &["#1#1####"]));
+        assert!(odds.exact);
+        let q = Some(0.25);
+        let expected = [q, None, Some(0.75), None, q, q, q, q];
+        for (x, want) in expected.into_iter().enumerate() {
+            match (odds.get(x, 0), want) {
+                (Some(got), Some(want)) => assert!((got - want).abs() < 1e-9, "({}, 0)", x),
+                (got, want) => assert_eq!(got, want, "({}, 0)", x),
+            }
+        }
+    }
+
+    #[test]
+    fn subset_rule_pairs_numbers_across_a_wrapped_edge() {
+        // On an 8x8 torus, (7, 3) and (1, 3) both touch column 0. Three
+        // cells are hidden; both numbers see one mine.
+        let hidden = [(0, 2), (0, 3), (2, 3)];
+        let mut cells = vec![Knowledge::Revealed(0); 64];
+        for (x, y) in hidden {
+            cells[y * 8 + x] = Knowledge::Hidden;
+        }
+        cells[3 * 8 + 7] = Knowledge::Revealed(1);
+        cells[3 * 8 + 1] = Knowledge::Revealed(1);
+        let view = PlayerView {
+            width: 8,
+            height: 8,
+            mine_count: 1,
+            topology: Topology::TORUS,
+            cells,
+        };
+        let found = subset_rule(&view, &view.constraints());
+        assert!(found.contains(&Deduction {
+            x: 2,
+            y: 3,
+            mine: false,
+            reason: Reason::Subset {
+                outer: (1, 3),
+                inner: (7, 3),
+            },
+        }));
+    }
+}
@@ -0,0 +1,250 @@
+//engine.topology.rs
+//! Which cells count as touching. Everything on the board that looks at
+//! adjacency (numbers, the safe area around the first click, flood fill,
+//! chording, 3BV and the solver) goes through the board's `Topology`, so a
+//! rule variant only has to say which offsets are its neighbours.
+
+use std::fmt;
+
+/// A neighbourhood rule. Implement it for a new variant and wrap a static
+/// instance with `Topology::new`.
+pub trait Neighborhood: Sync {
+    /// Identifies the variant in saves, replays and leaderboards.
+    fn name(&self) -> &'static str;
+
+    /// What players see when picking it.
+    fn label(&self) -> &'static str;
+
+    /// Offsets from `(x, y)` to the cells touching it.
+    fn offsets(&self, x: usize, y: usize) -> &[(isize, isize)];
+
+    /// Whether offsets past an edge come back in on the opposite side.
+    fn wraps(&self) -> bool {
+        false
+    }
+
+    fn grid(&self) -> Grid {
+        Grid::Square
+    }
+}
+
+/// The shape cells are drawn in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Grid {
+    Square,
+    /// Pointy-top hexagons, odd rows shifted half a cell to the right.
+    Hex,
+}
+
+/// Every cell within `radius` steps, diagonals included. `N` must be
+/// `(2 * radius + 1)² - 1`.
+const fn kernel<const N: usize>(radius: isize) -> [(isize, isize); N] {
+    let mut offsets = [(0, 0); N];
+    let mut i = 0;
+    let mut dy = -radius;
+    while dy <= radius {
+        let mut dx = -radius;
+        while dx <= radius {
+            if dx != 0 || dy != 0 {
+                offsets[i] = (dx, dy);
+                i += 1;
+            }
+            dx += 1;
+        }
+        dy += 1;
+    }
+    offsets
+}
+
+const SQUARE: [(isize, isize); 8] = kernel(1);
+const RADIUS_2: [(isize, isize); 24] = kernel(2);
+const CROSS: [(isize, isize); 4] = [(0, -1), (-1, 0), (1, 0), (0, 1)];
+const KNIGHT: [(isize, isize); 8] = [
+    (-1, -2),
+    (1, -2),
+    (-2, -1),
+    (2, -1),
+    (-2, 1),
+    (2, 1),
+    (-1, 2),
+    (1, 2),
+];
+const HEX_EVEN: [(isize, isize); 6] = [(-1, -1), (0, -1), (-1, 0), (1, 0), (-1, 1), (0, 1)];
+const HEX_ODD: [(isize, isize); 6] = [(0, -1), (1, -1), (-1, 0), (1, 0), (0, 1), (1, 1)];
+
+/// The same offsets around every cell of a square grid.
+struct Fixed {
+    name: &'static str,
+    label: &'static str,
+    offsets: &'static [(isize, isize)],
+    wraps: bool,
+}
+
+impl Neighborhood for Fixed {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn label(&self) -> &'static str {
+        self.label
+    }
+
+    fn offsets(&self, _x: usize, _y: usize) -> &[(isize, isize)] {
+        self.offsets
+    }
+
+    fn wraps(&self) -> bool {
+        self.wraps
+    }
+}
+
+struct Hex;
+
+impl Neighborhood for Hex {
+    fn name(&self) -> &'static str {
+        "hex"
+    }
+
+    fn label(&self) -> &'static str {
+        "Hexagons"
+    }
+
+    fn offsets(&self, _x: usize, y: usize) -> &[(isize, isize)] {
+        if y.is_multiple_of(2) {
+            &HEX_EVEN
+        } else {
+            &HEX_ODD
+        }
+    }
+
+    fn grid(&self) -> Grid {
+        Grid::Hex
+    }
+}
+
+/// A board's neighbourhood rule. Cheap to copy; two topologies are the
+/// same when their names are.
+#[derive(Clone, Copy)]
+pub struct Topology(&'static dyn Neighborhood);
+
+impl Topology {
+    pub const FLAT: Topology = Topology(&Fixed {
+        name: "flat",
+        label: "Squares",
+        offsets: &SQUARE,
+        wraps: false,
+    });
+    /// Each edge wraps around to the opposite one, so every cell, corners
+    /// included, has eight neighbours.
+    pub const TORUS: Topology = Topology(&Fixed {
+        name: "torus",
+        label: "Squares, edges wrap around",
+        offsets: &SQUARE,
+        wraps: true,
+    });
+    pub const HEX: Topology = Topology(&Hex);
+    /// Cells a chess knight's move away.
+    pub const KNIGHT: Topology = Topology(&Fixed {
+        name: "knight",
+        label: "Knight's moves",
+        offsets: &KNIGHT,
+        wraps: false,
+    });
+    /// Only the four cells sharing a side.
+    pub const CROSS: Topology = Topology(&Fixed {
+        name: "cross",
+        label: "Sides only (4 neighbours)",
+        offsets: &CROSS,
+        wraps: false,
+    });
+    /// Everything up to two cells away.
+    pub const RADIUS_2: Topology = Topology(&Fixed {
+        name: "radius-2",
+        label: "Two cells around (24 neighbours)",
+        offsets: &RADIUS_2,
+        wraps: false,
+    });
+
+    /// The built-in variants, the ones saves and replays can name.
+    pub const ALL: [Topology; 6] = [
+        Topology::FLAT,
+        Topology::TORUS,
+        Topology::HEX,
+        Topology::KNIGHT,
+        Topology::CROSS,
+        Topology::RADIUS_2,
+    ];
+
+    pub const fn new(rule: &'static dyn Neighborhood) -> Self {
+        Topology(rule)
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|t| t.name() == name)
+    }
+
+    pub fn name(self) -> &'static str {
+        self.0.name()
+    }
+
+    pub fn label(self) -> &'static str {
+        self.0.label()
+    }
+
+    pub fn offsets(self, x: usize, y: usize) -> &'static [(isize, isize)] {
+        self.0.offsets(x, y)
+    }
+
+    pub fn wraps(self) -> bool {
+        self.0.wraps()
+    }
+
+    pub fn grid(self) -> Grid {
+        self.0.grid()
+    }
+
+    /// The cells touching `(x, y)` on a `width` by `height` board, not
+    /// counting the cell itself.
+    pub fn neighbors(
+        self,
+        width: usize,
+        height: usize,
+        x: usize,
+        y: usize,
+    ) -> impl Iterator<Item = (usize, usize)> {
+        let (w, h) = (width as isize, height as isize);
+        let wraps = self.wraps();
+        self.offsets(x, y)
+            .iter()
+            .map(move |(dx, dy)| (x as isize + dx, y as isize + dy))
+            .filter_map(move |(nx, ny)| {
+                if wraps {
+                    Some((nx.rem_euclid(w), ny.rem_euclid(h)))
+                } else {
+                    (nx >= 0 && ny >= 0 && nx < w && ny < h).then_some((nx, ny))
+                }
+            })
+            .map(|(nx, ny)| (nx as usize, ny as usize))
+            .filter(move |&cell| cell != (x, y))
+    }
+}
+
+impl Default for Topology {
+    fn default() -> Self {
+        Topology::FLAT
+    }
+}
+
+impl PartialEq for Topology {
+    fn eq(&self, other: &Self) -> bool {
+        self.name() == other.name()
+    }
+}
+
+impl Eq for Topology {}
+
+impl fmt::Debug for Topology {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Topology({})", self.name())
+    }
+}
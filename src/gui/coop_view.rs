@@ -0,0 +1,128 @@
+//gui.coop_view.rs
+use super::layout::Layout;
+use super::paint_cell;
+use crate::coop::CoopClient;
+use eframe::egui;
+use minesweeper::engine::{GameStatus, Topology};
+
+/// Cursor colours, picked by player id.
+const PLAYER_COLORS: [egui::Color32; 6] = [
+    egui::Color32::from_rgb(30, 100, 230),
+    egui::Color32::from_rgb(230, 120, 0),
+    egui::Color32::from_rgb(20, 160, 60),
+    egui::Color32::from_rgb(170, 40, 200),
+    egui::Color32::from_rgb(0, 170, 170),
+    egui::Color32::from_rgb(200, 30, 90),
+];
+
+fn player_color(id: u32) -> egui::Color32 {
+    PLAYER_COLORS[id as usize % PLAYER_COLORS.len()]
+}
+
+/// The shared board of a co-op game. Clicks become messages to the host,
+/// and the cells only change when it answers.
+pub struct CoopView {
+    client: CoopClient,
+    /// The cell we last told the host we point at.
+    pointed: Option<(usize, usize)>,
+}
+
+impl CoopView {
+    pub fn new(client: CoopClient) -> Self {
+        Self {
+            client,
+            pointed: None,
+        }
+    }
+
+    /// Returns `false` once the player leaves the game.
+    pub fn show(&mut self, ui: &mut egui::Ui) -> bool {
+        self.client.poll();
+        let mut open = true;
+        ui.horizontal(|ui| {
+            if ui.button("Leave").clicked() {
+                open = false;
+            }
+            let finished = matches!(self.client.status, GameStatus::Won | GameStatus::Lost);
+            if ui
+                .add_enabled(finished, egui::Button::new("New board"))
+                .clicked()
+            {
+                self.client.new_game();
+            }
+            ui.label(self.client.status_line());
+        });
+        ui.horizontal(|ui| {
+            for partner in &self.client.partners {
+                let you = if Some(partner.id) == self.client.id {
+                    " (you)"
+                } else {
+                    ""
+                };
+                ui.colored_label(
+                    player_color(partner.id),
+                    format!("■ {}{}", partner.name, you),
+                );
+            }
+        });
+        if let Some(notice) = &self.client.notice {
+            ui.colored_label(egui::Color32::RED, notice);
+        }
+        if self.client.width == 0 {
+            return open;
+        }
+
+        let (width, height) = (self.client.width, self.client.height);
+        // Co-op hosts only deal out square boards.
+        let layout = Layout::fit(Topology::FLAT, width, height, ui.available_size());
+        let cell_size = layout.cell_size;
+        let (response, painter) =
+            ui.allocate_painter(layout.board_size(width, height), egui::Sense::click());
+        let origin = response.rect.min.to_vec2();
+        for y in 0..height {
+            for x in 0..width {
+                let (state, cell) = self.client.seen(x, y).parts();
+                let outline: Vec<_> = layout
+                    .outline(x, y)
+                    .into_iter()
+                    .map(|corner| corner + origin)
+                    .collect();
+                paint_cell(&painter, &state, &cell, 1, &outline, cell_size);
+            }
+        }
+
+        let hovered = response
+            .hover_pos()
+            .and_then(|pos| layout.cell_at(pos - origin, width, height));
+        if let Some((x, y)) = hovered {
+            if self.pointed != hovered {
+                self.pointed = hovered;
+                self.client.point_at(x, y);
+            }
+            if response.clicked() {
+                self.client.reveal(x, y);
+            } else if response.secondary_clicked() {
+                self.client.mark(x, y);
+            } else if response.middle_clicked() {
+                self.client.chord(x, y);
+            }
+        }
+
+        for partner in &self.client.partners {
+            let Some((x, y)) = partner.cursor.filter(|&(x, y)| x < width && y < height) else {
+                continue;
+            };
+            let rect = layout.cell_rect(x, y).translate(origin);
+            let color = player_color(partner.id);
+            painter.rect_stroke(rect.shrink(1.0), 0.0, egui::Stroke::new(3.0, color));
+            painter.text(
+                rect.left_top() + egui::vec2(2.0, 1.0),
+                egui::Align2::LEFT_TOP,
+                &partner.name,
+                egui::FontId::proportional((cell_size * 0.25).max(9.0)),
+                color,
+            );
+        }
+        open
+    }
+}
@@ -0,0 +1,157 @@
+//gui.endless_view.rs
+use super::paint_cell;
+use crate::saves;
+use eframe::egui;
+use minesweeper::engine::{Cell, CellState, Endless, GameStatus};
+
+const MIN_CELL_SIZE: f32 = 8.0;
+const MAX_CELL_SIZE: f32 = 64.0;
+
+/// The endless board: drag or use the arrow keys to pan, scroll to zoom.
+/// The world is saved when the player leaves it.
+pub struct EndlessView {
+    game: Endless,
+    /// The world position, in cells, at the middle of the view.
+    center: egui::Vec2,
+    cell_size: f32,
+    error: Option<String>,
+}
+
+impl EndlessView {
+    /// Carries on with the saved world, or starts a new one.
+    pub fn open() -> Self {
+        let (game, error) = match saves::load_endless() {
+            Ok(Some(game)) => (game, None),
+            Ok(None) => (Endless::new(rand::random()), None),
+            Err(e) => (
+                Endless::new(rand::random()),
+                Some(format!("Could not load the endless world: {}", e)),
+            ),
+        };
+        Self {
+            game,
+            center: egui::vec2(0.5, 0.5),
+            cell_size: 24.0,
+            error,
+        }
+    }
+
+    pub fn save(&self) {
+        if let Err(e) = saves::save_endless(&self.game) {
+            eprintln!("Failed to save the endless world: {}", e);
+        }
+    }
+
+    /// Returns `false` once the player leaves; the world is saved then.
+    pub fn show(&mut self, ui: &mut egui::Ui) -> bool {
+        let mut open = true;
+        ui.horizontal(|ui| {
+            if ui.button("Leave").clicked() {
+                open = false;
+            }
+            if ui.button("New world").clicked() {
+                self.game.restart(rand::random());
+                self.center = egui::vec2(0.5, 0.5);
+            }
+            if ui.button("Back to start").on_hover_text("Home").clicked()
+                || ui.input(|i| i.key_pressed(egui::Key::Home))
+            {
+                self.center = egui::vec2(0.5, 0.5);
+            }
+            ui.label(format!(
+                "Cleared: {}  Best: {}",
+                self.game.cleared(),
+                self.game.best()
+            ));
+            if self.game.status() == GameStatus::Lost {
+                ui.colored_label(egui::Color32::RED, "Hit a mine! Start a new world.");
+            }
+        });
+        ui.label("Drag or use the arrow keys to move around, scroll to zoom.");
+        if let Some(error) = &self.error {
+            ui.colored_label(egui::Color32::RED, error);
+        }
+
+        let (response, painter) =
+            ui.allocate_painter(ui.available_size(), egui::Sense::click_and_drag());
+        let rect = response.rect;
+        self.navigate(ui, &response);
+
+        // World position of the top-left corner of the view.
+        let origin = self.center - rect.size() / (2.0 * self.cell_size);
+        let to_screen =
+            |x: i64, y: i64| rect.min + (egui::vec2(x as f32, y as f32) - origin) * self.cell_size;
+        let (first_x, first_y) = (origin.x.floor() as i64, origin.y.floor() as i64);
+        let columns = (rect.width() / self.cell_size).ceil() as i64 + 1;
+        let rows = (rect.height() / self.cell_size).ceil() as i64 + 1;
+        for y in first_y..first_y + rows {
+            for x in first_x..first_x + columns {
+                let min = to_screen(x, y);
+                let max = min + egui::vec2(self.cell_size, self.cell_size);
+                let outline = [min, egui::pos2(max.x, min.y), max, egui::pos2(min.x, max.y)];
+                let state = self.game.cell_state(x, y);
+                let cell = self.game.cell(x, y).unwrap_or(Cell::Empty);
+                paint_cell(&painter, &state, &cell, 1, &outline, self.cell_size);
+            }
+        }
+
+        let hovered = response.hover_pos().map(|pos| {
+            let world = origin + (pos - rect.min) / self.cell_size;
+            (world.x.floor() as i64, world.y.floor() as i64)
+        });
+        if let Some((x, y)) = hovered {
+            if response.clicked() {
+                self.game.reveal(x, y);
+            } else if response.secondary_clicked() {
+                self.game.toggle_flag(x, y);
+            } else if response.middle_clicked() {
+                self.game.chord(x, y);
+            }
+            if self.game.cell_state(x, y) != CellState::Revealed {
+                let min = to_screen(x, y);
+                painter.rect_stroke(
+                    egui::Rect::from_min_size(min, egui::vec2(self.cell_size, self.cell_size)),
+                    0.0,
+                    egui::Stroke::new(2.0, egui::Color32::BLUE),
+                );
+            }
+        }
+
+        if !open {
+            self.save();
+        }
+        open
+    }
+
+    /// Pans with drags and the arrow keys, and zooms with the scroll wheel
+    /// around the point under the mouse.
+    fn navigate(&mut self, ui: &egui::Ui, response: &egui::Response) {
+        if response.dragged() {
+            self.center -= response.drag_delta() / self.cell_size;
+        }
+        let (step, scroll) = ui.input(|i| {
+            let key = |key, dx, dy| {
+                if i.key_pressed(key) {
+                    egui::vec2(dx, dy)
+                } else {
+                    egui::Vec2::ZERO
+                }
+            };
+            let step = key(egui::Key::ArrowLeft, -1.0, 0.0)
+                + key(egui::Key::ArrowRight, 1.0, 0.0)
+                + key(egui::Key::ArrowUp, 0.0, -1.0)
+                + key(egui::Key::ArrowDown, 0.0, 1.0);
+            (step, i.scroll_delta.y)
+        });
+        self.center += step;
+
+        if let Some(pointer) = response.hover_pos().filter(|_| scroll != 0.0) {
+            // Keep the world point under the mouse where it is.
+            let from_center = pointer - response.rect.center();
+            let before = self.center + from_center / self.cell_size;
+            self.cell_size =
+                (self.cell_size * (scroll / 200.0).exp()).clamp(MIN_CELL_SIZE, MAX_CELL_SIZE);
+            self.center = before - from_center / self.cell_size;
+        }
+    }
+}
@@ -0,0 +1,187 @@
+//gui.layout.rs
+use eframe::egui::{self, Pos2, Rect, Vec2};
+use minesweeper::engine::{Grid, Topology};
+
+/// Where cells sit on screen, relative to the board's top-left corner.
+/// Squares form a plain grid; hexagons point up and every odd row is
+/// pushed half a cell to the right, matching `Grid::Hex`.
+#[derive(Debug, Clone, Copy)]
+pub struct Layout {
+    /// Width of a cell; hexagons are measured across their flat sides.
+    pub cell_size: f32,
+    hex: bool,
+}
+
+impl Layout {
+    /// The largest cells that fit a `width` by `height` board into
+    /// `available`.
+    pub fn fit(topology: Topology, width: usize, height: usize, available: Vec2) -> Self {
+        let unit = Layout {
+            cell_size: 1.0,
+            hex: topology.grid() == Grid::Hex,
+        };
+        let size = unit.board_size(width, height);
+        Layout {
+            cell_size: (available.x / size.x).min(available.y / size.y),
+            ..unit
+        }
+    }
+
+    /// Distance from a hexagon's centre to its corners.
+    fn radius(&self) -> f32 {
+        self.cell_size / 3f32.sqrt()
+    }
+
+    pub fn board_size(&self, width: usize, height: usize) -> Vec2 {
+        if self.hex {
+            egui::vec2(
+                self.cell_size * (width as f32 + 0.5),
+                self.radius() * (1.5 * height as f32 + 0.5),
+            )
+        } else {
+            egui::vec2(width as f32, height as f32) * self.cell_size
+        }
+    }
+
+    pub fn center(&self, x: usize, y: usize) -> Pos2 {
+        if self.hex {
+            let shift = if y.is_multiple_of(2) { 0.5 } else { 1.0 };
+            egui::pos2(
+                self.cell_size * (x as f32 + shift),
+                self.radius() * (1.0 + 1.5 * y as f32),
+            )
+        } else {
+            (egui::vec2(x as f32 + 0.5, y as f32 + 0.5) * self.cell_size).to_pos2()
+        }
+    }
+
+    /// The box around a cell.
+    pub fn cell_rect(&self, x: usize, y: usize) -> Rect {
+        let height = if self.hex {
+            2.0 * self.radius()
+        } else {
+            self.cell_size
+        };
+        Rect::from_center_size(self.center(x, y), egui::vec2(self.cell_size, height))
+    }
+
+    /// The corners of a cell, in order around it.
+    pub fn outline(&self, x: usize, y: usize) -> Vec<Pos2> {
+        if !self.hex {
+            let rect = self.cell_rect(x, y);
+            return vec![
+                rect.left_top(),
+                rect.right_top(),
+                rect.right_bottom(),
+                rect.left_bottom(),
+            ];
+        }
+        let center = self.center(x, y);
+        (0..6)
+            .map(|i| {
+                let angle = (i as f32 * 60.0 - 90.0).to_radians();
+                center + egui::vec2(angle.cos(), angle.sin()) * self.radius()
+            })
+            .collect()
+    }
+
+    /// The cell under `pos`, if any.
+    pub fn cell_at(&self, pos: Pos2, width: usize, height: usize) -> Option<(usize, usize)> {
+        if pos.x < 0.0 || pos.y < 0.0 {
+            return None;
+        }
+        if !self.hex {
+            let (x, y) = (
+                (pos.x / self.cell_size) as usize,
+                (pos.y / self.cell_size) as usize,
+            );
+            return (x < width && y < height).then_some((x, y));
+        }
+        // The nearest centre owns the point; only the rows and columns
+        // next to a rough guess can be nearest.
+        let row = (pos.y / (1.5 * self.radius())) as usize;
+        let column = (pos.x / self.cell_size) as usize;
+        let (x, y) = (row.saturating_sub(1)..=row + 1)
+            .filter(|&y| y < height)
+            .flat_map(|y| (column.saturating_sub(1)..=column + 1).map(move |x| (x, y)))
+            .filter(|&(x, _)| x < width)
+            .min_by(|&a, &b| {
+                let distance = |(x, y)| self.center(x, y).distance_sq(pos);
+                distance(a).total_cmp(&distance(b))
+            })?;
+        let offset = pos - self.center(x, y);
+        let (dx, dy) = (offset.x.abs(), offset.y.abs());
+        let inside = dx <= self.cell_size / 2.0 && dy <= self.radius() - dx / 3f32.sqrt();
+        inside.then_some((x, y))
+    }
+}
+
+/// `outline` pulled `by` points towards its centre.
+pub fn inset(outline: &[Pos2], by: f32) -> Vec<Pos2> {
+    let center = centroid(outline);
+    outline
+        .iter()
+        .map(|&corner| {
+            let towards = center - corner;
+            corner + towards.normalized() * by.min(towards.length())
+        })
+        .collect()
+}
+
+pub fn centroid(outline: &[Pos2]) -> Pos2 {
+    let sum = outline
+        .iter()
+        .fold(Vec2::ZERO, |sum, corner| sum + corner.to_vec2());
+    (sum / outline.len().max(1) as f32).to_pos2()
+}
+
+pub fn fill(painter: &egui::Painter, outline: &[Pos2], color: egui::Color32) {
+    painter.add(egui::Shape::convex_polygon(
+        outline.to_vec(),
+        color,
+        egui::Stroke::NONE,
+    ));
+}
+
+pub fn stroke(painter: &egui::Painter, outline: &[Pos2], stroke: egui::Stroke) {
+    painter.add(egui::Shape::closed_line(outline.to_vec(), stroke));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// On hexagons, the cells drawn touching a cell, and hit beyond its
+    /// edges, must be exactly the ones `Topology::HEX` counts as its
+    /// neighbours, on even and odd rows.
+    #[test]
+    fn hex_layout_matches_hex_neighbours() {
+        let (width, height) = (5, 6);
+        let layout = Layout::fit(Topology::HEX, width, height, egui::vec2(500.0, 500.0));
+        for y in 0..height {
+            for x in 0..width {
+                let center = layout.center(x, y);
+                assert_eq!(layout.cell_at(center, width, height), Some((x, y)));
+
+                let mut touching: Vec<(usize, usize)> = (0..height)
+                    .flat_map(|ny| (0..width).map(move |nx| (nx, ny)))
+                    .filter(|&(nx, ny)| {
+                        let distance = layout.center(nx, ny).distance(center);
+                        (distance - layout.cell_size).abs() < 0.01
+                    })
+                    .collect();
+                let mut neighbours: Vec<(usize, usize)> =
+                    Topology::HEX.neighbors(width, height, x, y).collect();
+                touching.sort_unstable();
+                neighbours.sort_unstable();
+                assert_eq!(touching, neighbours, "cell ({}, {})", x, y);
+
+                // Just past the shared edge is the neighbour's.
+                for (nx, ny) in neighbours {
+                    let past_edge = center + (layout.center(nx, ny) - center) * 0.6;
+                    assert_eq!(layout.cell_at(past_edge, width, height), Some((nx, ny)));
+                }
+            }
+        }
+    }
+}
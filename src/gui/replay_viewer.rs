@@ -0,0 +1,147 @@
+//gui.replay_viewer.rs
+use super::layout::{self, Layout};
+use super::{paint_cell, stack};
+use eframe::egui;
+use minesweeper::engine::replay::Replay;
+use minesweeper::engine::{Board, GameStatus};
+use std::time::Duration;
+
+const SPEEDS: [f32; 5] = [0.25, 0.5, 1.0, 2.0, 4.0];
+
+/// Plays a recorded game back by feeding its moves to a fresh `Board`.
+pub struct ReplayViewer {
+    replay: Replay,
+    board: Board,
+    /// Number of events already applied to `board`.
+    applied: usize,
+    position: Duration,
+    playing: bool,
+    speed: f32,
+}
+
+impl ReplayViewer {
+    pub fn new(replay: Replay) -> Self {
+        let board = replay.start();
+        Self {
+            replay,
+            board,
+            applied: 0,
+            position: Duration::ZERO,
+            playing: true,
+            speed: 1.0,
+        }
+    }
+
+    fn seek(&mut self, to: Duration) {
+        let to = to.min(self.replay.duration());
+        if to < self.position {
+            (self.board, self.applied) = self.replay.board_at(to);
+        } else {
+            while let Some(event) = self.replay.events.get(self.applied) {
+                if event.at > to {
+                    break;
+                }
+                Replay::apply(&mut self.board, event);
+                self.applied += 1;
+            }
+        }
+        self.position = to;
+    }
+
+    /// Advances playback by `elapsed` of wall-clock time.
+    pub fn tick(&mut self, elapsed: Duration) {
+        if self.playing {
+            self.seek(self.position + elapsed.mul_f32(self.speed));
+            if self.position >= self.replay.duration() {
+                self.playing = false;
+            }
+        }
+    }
+
+    /// Returns `false` once the player closes the viewer.
+    pub fn show(&mut self, ui: &mut egui::Ui) -> bool {
+        let mut open = true;
+        ui.horizontal(|ui| {
+            if ui.button("Close replay").clicked() {
+                open = false;
+            }
+            let label = if self.playing { "Pause" } else { "Play" };
+            if ui.button(label).clicked() || ui.input(|i| i.key_pressed(egui::Key::Space)) {
+                if !self.playing && self.position >= self.replay.duration() {
+                    self.seek(Duration::ZERO);
+                }
+                self.playing = !self.playing;
+            }
+            egui::ComboBox::from_label("Speed")
+                .selected_text(format!("{}x", self.speed))
+                .show_ui(ui, |ui| {
+                    for speed in SPEEDS {
+                        ui.selectable_value(&mut self.speed, speed, format!("{}x", speed));
+                    }
+                });
+            ui.label(format!("Seed: {}", self.replay.seed));
+        });
+
+        let total = self.replay.duration().as_secs_f32();
+        let mut position = self.position.as_secs_f32();
+        let slider = ui.add(
+            egui::Slider::new(&mut position, 0.0..=total)
+                .suffix("s")
+                .text(format!("of {:.1}s", total)),
+        );
+        if slider.changed() {
+            self.seek(Duration::from_secs_f32(position));
+        }
+        ui.label(format!(
+            "Move {}/{}{}",
+            self.applied,
+            self.replay.events.len(),
+            match self.board.status() {
+                GameStatus::Won => " - won",
+                GameStatus::Lost => " - lost",
+                GameStatus::Ready | GameStatus::Playing => "",
+            }
+        ));
+
+        let (width, height) = (self.board.width(), self.board.height());
+        let layout = Layout::fit(self.board.topology(), width, height, ui.available_size());
+        let (response, painter) =
+            ui.allocate_painter(layout.board_size(width, height), egui::Sense::hover());
+        let origin = response.rect.min.to_vec2();
+        let outline = |x: usize, y: usize| -> Vec<egui::Pos2> {
+            layout
+                .outline(x, y)
+                .into_iter()
+                .map(|corner| corner + origin)
+                .collect()
+        };
+        for y in 0..height {
+            for x in 0..width {
+                paint_cell(
+                    &painter,
+                    self.board.cell_state(x, y),
+                    self.board.cell(x, y),
+                    stack(&self.board, x, y),
+                    &outline(x, y),
+                    layout.cell_size,
+                );
+            }
+        }
+
+        if let Some(last) = self.applied.checked_sub(1).map(|i| self.replay.events[i]) {
+            layout::stroke(
+                &painter,
+                &outline(last.x, last.y),
+                egui::Stroke::new(2.0, egui::Color32::BLUE),
+            );
+            if let Some((px, py)) = last.pointer {
+                painter.circle_filled(
+                    response.rect.min + egui::vec2(px, py) * layout.cell_size,
+                    (layout.cell_size * 0.15).max(3.0),
+                    egui::Color32::from_rgba_unmultiplied(0, 0, 255, 160),
+                );
+            }
+        }
+        open
+    }
+}
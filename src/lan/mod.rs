@@ -0,0 +1,145 @@
+//lan.mod.rs
+//! Line-based TCP plumbing shared by races and co-op games. Every
+//! connection gets a reader thread that parses lines and forwards them to
+//! one channel, so the host and clients handle the network in their own
+//! loop.
+
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::Duration;
+
+/// How long a host waits for a player's connection to take a message. One
+/// stalled player must not hold up everyone else.
+const WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub type Parser<M> = fn(&str) -> Result<M, String>;
+
+/// What a host hears from its connections.
+pub enum Event<M> {
+    Connected(u32, TcpStream),
+    Message(u32, M),
+    Disconnected(u32),
+}
+
+/// Accepts connections on `port` in the background.
+pub fn listen<M: Send + 'static>(port: u16, parse: Parser<M>) -> io::Result<Receiver<Event<M>>> {
+    let listener = TcpListener::bind(("0.0.0.0", port))?;
+    let (events, inbox) = mpsc::channel();
+    thread::spawn(move || accept(listener, events, parse));
+    Ok(inbox)
+}
+
+fn accept<M: Send + 'static>(listener: TcpListener, events: Sender<Event<M>>, parse: Parser<M>) {
+    for (id, stream) in (1..).zip(listener.incoming()) {
+        let Ok(stream) = stream else {
+            continue;
+        };
+        if stream.set_write_timeout(Some(WRITE_TIMEOUT)).is_err() {
+            continue;
+        }
+        let Ok(reader) = stream.try_clone() else {
+            continue;
+        };
+        if events.send(Event::Connected(id, stream)).is_err() {
+            return;
+        }
+        let events = events.clone();
+        thread::spawn(move || {
+            let (sender, messages) = mpsc::channel();
+            thread::spawn(move || read(reader, sender, parse));
+            for message in messages {
+                if events.send(Event::Message(id, message)).is_err() {
+                    return;
+                }
+            }
+            let _ = events.send(Event::Disconnected(id));
+        });
+    }
+}
+
+/// Writes one message to a player. A connection that cannot take it, for
+/// instance because the write timed out, is shut down; its reader then
+/// reports the player as disconnected.
+pub fn send_line(stream: &mut TcpStream, line: &str) {
+    if writeln!(stream, "{}", line).is_err() {
+        let _ = stream.shutdown(Shutdown::Both);
+    }
+}
+
+/// Connects to a host; the receiver closes when the host goes away.
+pub fn connect<M: Send + 'static>(
+    address: &str,
+    parse: Parser<M>,
+) -> io::Result<(TcpStream, Receiver<M>)> {
+    let stream = TcpStream::connect(address)?;
+    let reader = stream.try_clone()?;
+    let (sender, messages) = mpsc::channel();
+    thread::spawn(move || read(reader, sender, parse));
+    Ok((stream, messages))
+}
+
+fn read<M>(stream: TcpStream, sender: Sender<M>, parse: Parser<M>) {
+    for line in BufReader::new(stream).lines() {
+        let Ok(line) = line else {
+            return;
+        };
+        match parse(&line) {
+            Ok(message) => {
+                if sender.send(message).is_err() {
+                    return;
+                }
+            }
+            Err(e) => eprintln!("Ignoring a bad message: {}", e),
+        }
+    }
+}
+
+/// Adds the configured port to `address` when it has none.
+pub fn with_port(address: &str, port: u16) -> String {
+    if address.contains(':') {
+        address.to_string()
+    } else {
+        format!("{}:{}", address, port)
+    }
+}
+
+/// `line` after its first word `word`, trimmed.
+pub fn rest(line: &str, word: &str) -> String {
+    line.trim_start()
+        .strip_prefix(word)
+        .unwrap_or("")
+        .trim()
+        .to_string()
+}
+
+/// Keeps `text` on the one line a message has.
+pub fn one_line(text: &str) -> String {
+    text.replace(['\n', '\r'], " ")
+}
+
+pub fn number<T: std::str::FromStr>(word: &str) -> Result<T, String> {
+    word.parse().map_err(|_| format!("bad number {:?}", word))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    #[test]
+    fn a_failed_write_disconnects_the_player() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let _client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (mut server, _) = listener.accept().unwrap();
+        let mut reader = server.try_clone().unwrap();
+        reader
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        server.shutdown(Shutdown::Write).unwrap();
+        send_line(&mut server, "status won");
+        // The reader sees the end of the connection, not a timeout.
+        assert_eq!(reader.read(&mut [0; 16]).unwrap(), 0);
+    }
+}
@@ -1,6 +1,66 @@
 mod gui;
 mod db;
+mod config;
+mod coop;
+mod daily;
+mod paths;
+mod lan;
+mod positions;
+mod race;
+mod replays;
+mod saves;
+mod stats;
+mod tui;
+
+use config::{Config, ConfigError};
+
+enum Command {
+    Gui,
+    Tui,
+    Host,
+    Coop,
+    Join(String),
+}
 
 fn main() -> Result<(), eframe::Error> {
-    gui::run()
-}
\ No newline at end of file
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let command = match args.first().map(String::as_str) {
+        Some("tui") => Command::Tui,
+        Some("host") => Command::Host,
+        Some("coop") => Command::Coop,
+        Some("join") if args.len() > 1 && !args[1].starts_with('-') => {
+            Command::Join(args.remove(1))
+        }
+        Some("join") => {
+            eprintln!("minesweeper: join needs the host's address (see --help)");
+            std::process::exit(2);
+        }
+        _ => Command::Gui,
+    };
+    if !matches!(command, Command::Gui) {
+        args.remove(0);
+    }
+    let config = match Config::load(args) {
+        Ok(config) => config,
+        Err(ConfigError::HelpRequested) => {
+            println!("{}", config::USAGE);
+            return Ok(());
+        }
+        Err(e) => {
+            eprintln!("minesweeper: {}", e);
+            std::process::exit(2);
+        }
+    };
+    let result = match command {
+        Command::Gui => return gui::run(config),
+        Command::Tui => tui::run(config),
+        Command::Host => race::host(&config),
+        Command::Coop => coop::host(&config),
+        Command::Join(address) => tui::race(config, &address),
+    };
+    if let Err(e) = result {
+        eprintln!("minesweeper: {}", e);
+        std::process::exit(1);
+    }
+    Ok(())
+}
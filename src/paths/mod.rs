@@ -0,0 +1,29 @@
+//paths.mod.rs
+use std::env;
+use std::path::PathBuf;
+
+const APP_DIR: &str = "minesweeper";
+
+/// Per-user directory for data the game writes itself (scores, saves).
+pub fn data_dir() -> Option<PathBuf> {
+    base_dir("XDG_DATA_HOME", &[".local", "share"]).map(|dir| dir.join(APP_DIR))
+}
+
+/// Per-user directory for settings the player edits by hand.
+pub fn config_dir() -> Option<PathBuf> {
+    base_dir("XDG_CONFIG_HOME", &[".config"]).map(|dir| dir.join(APP_DIR))
+}
+
+fn base_dir(xdg_var: &str, home_fallback: &[&str]) -> Option<PathBuf> {
+    if let Some(dir) = env::var_os(xdg_var).filter(|dir| !dir.is_empty()) {
+        return Some(PathBuf::from(dir));
+    }
+    if cfg!(windows) {
+        return env::var_os("APPDATA").map(PathBuf::from);
+    }
+    let home = PathBuf::from(env::var_os("HOME")?);
+    if cfg!(target_os = "macos") {
+        return Some(home.join("Library").join("Application Support"));
+    }
+    Some(home_fallback.iter().fold(home, |dir, part| dir.join(part)))
+}
@@ -0,0 +1,41 @@
+//positions.mod.rs
+//! Positions exported for sharing, kept in the `positions` folder in the
+//! data directory.
+
+use crate::paths;
+use minesweeper::engine::{position, Board};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Writes the position as text, plus an `.mbf` layout when the board fits
+/// that format, and returns the text file's path.
+pub fn export(board: &Board) -> io::Result<PathBuf> {
+    let text = position::to_text(board)
+        .map_err(|message| io::Error::new(io::ErrorKind::InvalidInput, message))?;
+    let dir = paths::data_dir()
+        .map(|dir| dir.join("positions"))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no data directory"))?;
+    fs::create_dir_all(&dir)?;
+    let stamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let path = dir.join(format!("{}-{}.txt", stamp, board.seed()));
+    fs::write(&path, text)?;
+    if let Ok(mbf) = position::to_mbf(board) {
+        fs::write(path.with_extension("mbf"), mbf)?;
+    }
+    Ok(path)
+}
+
+/// Reads a text position or an MBF layout.
+pub fn import(path: &Path) -> io::Result<Board> {
+    let bytes = fs::read(path)?;
+    position::import(&bytes).map_err(|message| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{}: {}", path.display(), message),
+        )
+    })
+}
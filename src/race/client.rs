@@ -0,0 +1,197 @@
+//race.client.rs
+use super::protocol::{Start, ToClient, ToHost};
+use crate::lan;
+use minesweeper::engine::{Board, GameStatus};
+use std::collections::HashSet;
+use std::io::{self, Write};
+use std::net::TcpStream;
+use std::sync::mpsc::{Receiver, TryRecvError};
+use std::time::Duration;
+
+/// Another player as this client last heard from the host.
+#[derive(Debug, Clone)]
+pub struct Racer {
+    pub id: u32,
+    pub name: String,
+    pub percent: u8,
+    /// Whether they won, and their time.
+    pub finished: Option<(bool, Duration)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Winner(u32),
+    NoWinner,
+}
+
+/// A connection to a race host and what it has reported so far. The
+/// frontends poll it once per frame and report the board after moves.
+pub struct RaceClient {
+    stream: TcpStream,
+    incoming: Receiver<ToClient>,
+    pub id: Option<u32>,
+    pub racers: Vec<Racer>,
+    pub start: Option<Start>,
+    pub outcome: Option<Outcome>,
+    /// The host refused us or the connection dropped.
+    pub error: Option<String>,
+    /// Cells already reported as opened.
+    sent: HashSet<(usize, usize)>,
+    /// The end of our game has been reported; nothing more is sent.
+    reported_end: bool,
+}
+
+impl RaceClient {
+    pub fn join(address: &str, name: &str) -> io::Result<Self> {
+        let (mut stream, incoming) = lan::connect(address, ToClient::parse)?;
+        writeln!(
+            stream,
+            "{}",
+            ToHost::Hello {
+                name: name.to_string()
+            }
+            .encode()
+        )?;
+        Ok(Self {
+            stream,
+            incoming,
+            id: None,
+            racers: Vec::new(),
+            start: None,
+            outcome: None,
+            error: None,
+            sent: HashSet::new(),
+            reported_end: false,
+        })
+    }
+
+    /// Handles everything the host sent since the last call. Returns the
+    /// race's board once, when it starts.
+    pub fn poll(&mut self) -> Option<Start> {
+        let mut started = None;
+        loop {
+            let message = match self.incoming.try_recv() {
+                Ok(message) => message,
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    if self.outcome.is_none() && self.error.is_none() {
+                        self.error = Some("lost the connection to the host".to_string());
+                    }
+                    break;
+                }
+            };
+            match message {
+                ToClient::Welcome { id } => self.id = Some(id),
+                ToClient::Joined { id, name } => {
+                    if !self.racers.iter().any(|r| r.id == id) {
+                        self.racers.push(Racer {
+                            id,
+                            name,
+                            percent: 0,
+                            finished: None,
+                        });
+                    }
+                }
+                ToClient::Left { id } => self.racers.retain(|r| r.id != id),
+                ToClient::Start(start) => {
+                    self.start = Some(start);
+                    started = Some(start);
+                }
+                ToClient::Progress { id, percent } => {
+                    if let Some(racer) = self.racer_mut(id) {
+                        racer.percent = percent;
+                    }
+                }
+                ToClient::Finished { id, won, millis } => {
+                    if let Some(racer) = self.racer_mut(id) {
+                        racer.finished = Some((won, Duration::from_millis(millis)));
+                    }
+                }
+                ToClient::Winner { id } => self.outcome = Some(Outcome::Winner(id)),
+                ToClient::NoWinner => self.outcome = Some(Outcome::NoWinner),
+                ToClient::Error { message } => self.error = Some(message),
+            }
+        }
+        started
+    }
+
+    fn racer_mut(&mut self, id: u32) -> Option<&mut Racer> {
+        self.racers.iter_mut().find(|r| r.id == id)
+    }
+
+    /// Sends the host the safe cells opened since the last report; the
+    /// host works out progress, wins and losses from them. A finished board
+    /// shows every mine, so only a loss sends one, and only once.
+    pub fn report(&mut self, board: &Board) {
+        if self.start.is_none() || self.outcome.is_some() || self.reported_end {
+            return;
+        }
+        let revealed = (0..board.height())
+            .flat_map(|y| (0..board.width()).map(move |x| (x, y)))
+            .filter(|&(x, y)| board.is_revealed(x, y));
+        let (mines, safe): (Vec<_>, Vec<_>) =
+            revealed.partition(|&(x, y)| board.mines_at(x, y) > 0);
+        let mut cells: Vec<(usize, usize)> = safe
+            .into_iter()
+            .filter(|cell| !self.sent.contains(cell))
+            .collect();
+        if board.status() == GameStatus::Lost {
+            cells.extend(mines.first());
+        }
+        self.reported_end = board.is_finished();
+        if cells.is_empty() {
+            return;
+        }
+        self.sent.extend(&cells);
+        let message = ToHost::Opened { cells };
+        if let Err(e) = writeln!(self.stream, "{}", message.encode()) {
+            self.error = Some(format!("lost the connection to the host: {}", e));
+        }
+    }
+
+    /// One line on how the race stands.
+    pub fn status(&self) -> String {
+        if let Some(error) = &self.error {
+            return format!("Race: {}", error);
+        }
+        match self.outcome {
+            Some(Outcome::Winner(id)) if Some(id) == self.id => "You won the race!".to_string(),
+            Some(Outcome::Winner(id)) => format!("{} won the race.", self.name_of(id)),
+            Some(Outcome::NoWinner) => "Nobody cleared the board.".to_string(),
+            None if self.start.is_none() => {
+                format!(
+                    "Waiting for the race to start ({} joined).",
+                    self.racers.len()
+                )
+            }
+            None => "Race on!".to_string(),
+        }
+    }
+
+    /// One line per player: name, progress and result.
+    pub fn standings(&self) -> Vec<String> {
+        self.racers
+            .iter()
+            .map(|racer| {
+                let you = if Some(racer.id) == self.id {
+                    " (you)"
+                } else {
+                    ""
+                };
+                let state = match racer.finished {
+                    Some((true, time)) => format!("cleared in {:.2}s", time.as_secs_f32()),
+                    Some((false, _)) => "hit a mine".to_string(),
+                    None => format!("{:>3}%", racer.percent),
+                };
+                format!("{}{}: {}", racer.name, you, state)
+            })
+            .collect()
+    }
+
+    fn name_of(&self, id: u32) -> &str {
+        self.racers
+            .iter()
+            .find(|r| r.id == id)
+            .map_or("Someone", |r| r.name.as_str())
+    }
+}
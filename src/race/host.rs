@@ -0,0 +1,360 @@
+//race.host.rs
+//! The race server. One loop owns the race, handles what the players'
+//! connections send and writes back to them. The host runs a single race
+//! and exits once it is decided.
+//!
+//! Players only say which cells they opened. The host holds the layout,
+//! so it decides who hit a mine and who cleared the board, and when.
+
+use super::protocol::{Start, ToClient, ToHost};
+use crate::config::Config;
+use crate::lan::{self, Event};
+use minesweeper::engine::{Board, Difficulty};
+use rand::Rng;
+use std::collections::HashSet;
+use std::io::{self, Write};
+use std::net::{Shutdown, TcpStream};
+use std::time::Instant;
+
+struct Player {
+    id: u32,
+    stream: TcpStream,
+    /// Set by `hello`; players without one are not in the race yet.
+    name: Option<String>,
+    /// The safe cells they have opened.
+    opened: HashSet<(usize, usize)>,
+    percent: u8,
+    finished: Option<bool>,
+}
+
+/// The race under way: the board everyone plays and when it was handed out.
+struct Race {
+    layout: Board,
+    safe: usize,
+    started: Instant,
+}
+
+struct Host {
+    difficulty: Difficulty,
+    wanted: usize,
+    players: Vec<Player>,
+    race: Option<Race>,
+}
+
+/// Hosts one race on `config.race_port` and prints how it goes.
+pub fn run(config: &Config) -> io::Result<()> {
+    let inbox = lan::listen(config.race_port, ToHost::parse)?;
+    println!(
+        "Hosting a race on port {}: {}, waiting for {} players.",
+        config.race_port, config.difficulty, config.race_players
+    );
+
+    let mut host = Host {
+        difficulty: config.difficulty,
+        wanted: config.race_players,
+        players: Vec::new(),
+        race: None,
+    };
+    for event in inbox {
+        if host.handle(event) {
+            break;
+        }
+    }
+    Ok(())
+}
+
+impl Host {
+    /// Returns true once the race is decided.
+    fn handle(&mut self, event: Event<ToHost>) -> bool {
+        match event {
+            Event::Connected(id, mut stream) => {
+                if self.race.is_some() {
+                    let refusal = ToClient::Error {
+                        message: "a race is already running".to_string(),
+                    };
+                    let _ = writeln!(stream, "{}", refusal.encode());
+                    let _ = stream.shutdown(Shutdown::Both);
+                } else {
+                    self.players.push(Player {
+                        id,
+                        stream,
+                        name: None,
+                        opened: HashSet::new(),
+                        percent: 0,
+                        finished: None,
+                    });
+                }
+                false
+            }
+            Event::Message(id, ToHost::Hello { name }) => {
+                if self.race.is_some() {
+                    return false;
+                }
+                let Some(player) = self.players.iter_mut().find(|p| p.id == id) else {
+                    return false;
+                };
+                player.name = Some(name.clone());
+                self.send(id, &ToClient::Welcome { id });
+                let others: Vec<ToClient> = self
+                    .racers()
+                    .filter(|p| p.id != id)
+                    .map(|p| ToClient::Joined {
+                        id: p.id,
+                        name: p.name.clone().unwrap_or_default(),
+                    })
+                    .collect();
+                for message in &others {
+                    self.send(id, message);
+                }
+                self.broadcast(&ToClient::Joined {
+                    id,
+                    name: name.clone(),
+                });
+                let joined = self.racers().count();
+                println!("{} joined ({}/{}).", name, joined, self.wanted);
+                if joined >= self.wanted {
+                    self.start();
+                }
+                false
+            }
+            Event::Message(id, ToHost::Opened { cells }) => self.opened(id, &cells),
+            Event::Disconnected(id) => {
+                let Some(i) = self.players.iter().position(|p| p.id == id) else {
+                    return false;
+                };
+                let player = self.players.remove(i);
+                if let Some(name) = player.name {
+                    println!("{} left.", name);
+                    self.broadcast(&ToClient::Left { id });
+                }
+                self.race.is_some() && self.check_no_winner()
+            }
+        }
+    }
+
+    /// Checks the cells a player opened against the layout. Opening every
+    /// safe cell wins, timed by the host's clock, even if mines came along
+    /// with the last ones; otherwise opening a mine loses.
+    fn opened(&mut self, id: u32, cells: &[(usize, usize)]) -> bool {
+        let Some(race) = &self.race else {
+            return false;
+        };
+        let Some(player) = self.players.iter_mut().find(|p| p.id == id) else {
+            return false;
+        };
+        if player.finished.is_some() {
+            return false;
+        }
+        let layout = &race.layout;
+        let mut hit_mine = false;
+        for &(x, y) in cells.iter().filter(|&&(x, y)| layout.contains(x, y)) {
+            if layout.mines_at(x, y) > 0 {
+                hit_mine = true;
+            } else {
+                player.opened.insert((x, y));
+            }
+        }
+        let won = player.opened.len() == race.safe;
+        if !hit_mine && !won {
+            let percent = (player.opened.len() * 100 / race.safe) as u8;
+            if percent != player.percent {
+                player.percent = percent;
+                self.broadcast(&ToClient::Progress { id, percent });
+            }
+            return false;
+        }
+
+        let millis = race.started.elapsed().as_millis() as u64;
+        player.finished = Some(won);
+        let name = player.name.clone().unwrap_or_default();
+        self.broadcast(&ToClient::Finished { id, won, millis });
+        if won {
+            println!("{} won in {:.2}s.", name, millis as f32 / 1000.0);
+            self.broadcast(&ToClient::Winner { id });
+            return true;
+        }
+        println!("{} hit a mine.", name);
+        self.check_no_winner()
+    }
+
+    fn racers(&self) -> impl Iterator<Item = &Player> {
+        self.players.iter().filter(|p| p.name.is_some())
+    }
+
+    fn start(&mut self) {
+        let (width, height, mines) = self.difficulty.dimensions();
+        let mut rng = rand::thread_rng();
+        let start = Start {
+            width,
+            height,
+            mines,
+            seed: rng.gen(),
+            first_click: (rng.gen_range(0..width), rng.gen_range(0..height)),
+        };
+        let mut layout = start.board();
+        // Opening the first click places the mines, as it does for players.
+        let _ = layout.reveal(start.first_click.0, start.first_click.1);
+        let safe = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .filter(|&(x, y)| layout.mines_at(x, y) == 0)
+            .count();
+        self.race = Some(Race {
+            layout,
+            safe,
+            started: Instant::now(),
+        });
+        // Connections that never said hello have no place in the race.
+        for player in self.players.iter().filter(|p| p.name.is_none()) {
+            let _ = player.stream.shutdown(Shutdown::Both);
+        }
+        self.players.retain(|p| p.name.is_some());
+        self.broadcast(&ToClient::Start(start));
+        println!("The race is on (seed {}).", start.seed);
+    }
+
+    /// Ends the race when nobody is left who could still win.
+    fn check_no_winner(&mut self) -> bool {
+        if self.players.iter().any(|p| p.finished.is_none()) {
+            return false;
+        }
+        self.broadcast(&ToClient::NoWinner);
+        println!("Nobody cleared the board.");
+        true
+    }
+
+    fn send(&mut self, id: u32, message: &ToClient) {
+        if let Some(player) = self.players.iter_mut().find(|p| p.id == id) {
+            lan::send_line(&mut player.stream, &message.encode());
+        }
+    }
+
+    fn broadcast(&mut self, message: &ToClient) {
+        let line = message.encode();
+        for player in self.players.iter_mut().filter(|p| p.name.is_some()) {
+            lan::send_line(&mut player.stream, &line);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader};
+    use std::net::TcpListener;
+
+    type Cells = Vec<(usize, usize)>;
+
+    /// A host waiting for one player, and that player's end of a localhost
+    /// connection after it said hello.
+    fn joined() -> (Host, BufReader<TcpStream>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (server, _) = listener.accept().unwrap();
+        let mut host = Host {
+            difficulty: Difficulty::Easy,
+            wanted: 1,
+            players: Vec::new(),
+            race: None,
+        };
+        assert!(!host.handle(Event::Connected(1, server)));
+        let hello = ToHost::Hello {
+            name: "Ada".to_string(),
+        };
+        assert!(!host.handle(Event::Message(1, hello)));
+        (host, BufReader::new(client))
+    }
+
+    fn receive(client: &mut BufReader<TcpStream>) -> ToClient {
+        let mut line = String::new();
+        client.read_line(&mut line).unwrap();
+        ToClient::parse(line.trim_end()).unwrap()
+    }
+
+    /// Reads up to the start of the race and returns the layout's cells,
+    /// split into safe ones and mines.
+    fn start(client: &mut BufReader<TcpStream>) -> (Cells, Cells) {
+        assert_eq!(receive(client), ToClient::Welcome { id: 1 });
+        assert!(matches!(receive(client), ToClient::Joined { id: 1, .. }));
+        let ToClient::Start(start) = receive(client) else {
+            panic!("expected the race to start");
+        };
+        let mut board = start.board();
+        let _ = board.reveal(start.first_click.0, start.first_click.1);
+        let cells = (0..start.height).flat_map(|y| (0..start.width).map(move |x| (x, y)));
+        cells.partition(|&(x, y)| board.mines_at(x, y) == 0)
+    }
+
+    #[test]
+    fn opening_every_safe_cell_wins() {
+        let (mut host, mut client) = joined();
+        let (safe, _) = start(&mut client);
+        let (first, rest) = safe.split_at(safe.len() / 2);
+        let opened = |cells: &[(usize, usize)]| {
+            Event::Message(
+                1,
+                ToHost::Opened {
+                    cells: cells.to_vec(),
+                },
+            )
+        };
+        assert!(!host.handle(opened(first)));
+        assert!(matches!(
+            receive(&mut client),
+            ToClient::Progress { id: 1, .. }
+        ));
+        assert!(host.handle(opened(rest)));
+        assert!(matches!(
+            receive(&mut client),
+            ToClient::Finished {
+                id: 1,
+                won: true,
+                ..
+            }
+        ));
+        assert_eq!(receive(&mut client), ToClient::Winner { id: 1 });
+    }
+
+    #[test]
+    fn clearing_the_board_wins_with_the_mines_it_shows() {
+        let (mut host, mut client) = joined();
+        let (mut cells, mines) = start(&mut client);
+        cells.extend(mines);
+        assert!(host.handle(Event::Message(1, ToHost::Opened { cells })));
+        assert!(matches!(
+            receive(&mut client),
+            ToClient::Finished {
+                id: 1,
+                won: true,
+                ..
+            }
+        ));
+        assert_eq!(receive(&mut client), ToClient::Winner { id: 1 });
+    }
+
+    #[test]
+    fn opening_a_mine_loses() {
+        let (mut host, mut client) = joined();
+        let (_, mines) = start(&mut client);
+        let cells = vec![mines[0]];
+        assert!(host.handle(Event::Message(1, ToHost::Opened { cells })));
+        assert!(matches!(
+            receive(&mut client),
+            ToClient::Finished {
+                id: 1,
+                won: false,
+                ..
+            }
+        ));
+        assert_eq!(receive(&mut client), ToClient::NoWinner);
+    }
+
+    #[test]
+    fn opened_cells_round_trip() {
+        let message = ToHost::Opened {
+            cells: vec![(0, 1), (12, 7)],
+        };
+        assert_eq!(message.encode(), "opened 0 1 12 7");
+        assert_eq!(ToHost::parse(&message.encode()), Ok(message));
+        assert!(ToHost::parse("opened 3").is_err());
+    }
+}
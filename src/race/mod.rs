@@ -0,0 +1,12 @@
+//race.mod.rs
+//! Head-to-head races on a local network. A host started with
+//! `minesweeper host` hands every player the same board and first click,
+//! relays everyone's progress and declares the first to clear it the
+//! winner.
+
+mod client;
+mod host;
+mod protocol;
+
+pub use client::RaceClient;
+pub use host::run as host;
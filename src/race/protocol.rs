@@ -0,0 +1,154 @@
+//race.protocol.rs
+//! Messages between race clients and the host, one per line of text.
+//!
+//! Clients send `hello NAME` and `opened X Y X Y ...` with the safe cells
+//! they opened since the last message, plus the mine when they hit one.
+//! The host checks those against the layout and times the finish itself.
+//! Every client gets the seed, so this only guards against a frontend
+//! misreporting by mistake, not against a player who wants to cheat.
+//! The host answers with `welcome ID`,
+//! `joined ID NAME`, `left ID`, `start W H MINES SEED X Y`,
+//! `progress ID PERCENT`, `finished ID won|lost MILLIS`, `winner ID`,
+//! `no-winner` and `error TEXT`.
+
+use crate::lan::{number, one_line, rest};
+use minesweeper::engine::Board;
+
+/// The board everyone races on. `first_click` is opened for every player
+/// so the layouts match.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Start {
+    pub width: usize,
+    pub height: usize,
+    pub mines: usize,
+    pub seed: u64,
+    pub first_click: (usize, usize),
+}
+
+impl Start {
+    pub fn board(&self) -> Board {
+        Board::with_seed(self.width, self.height, self.mines, self.seed)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ToHost {
+    Hello { name: String },
+    Opened { cells: Vec<(usize, usize)> },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ToClient {
+    Welcome { id: u32 },
+    Joined { id: u32, name: String },
+    Left { id: u32 },
+    Start(Start),
+    Progress { id: u32, percent: u8 },
+    Finished { id: u32, won: bool, millis: u64 },
+    Winner { id: u32 },
+    NoWinner,
+    Error { message: String },
+}
+
+impl ToHost {
+    pub fn encode(&self) -> String {
+        match self {
+            ToHost::Hello { name } => format!("hello {}", one_line(name)),
+            ToHost::Opened { cells } => cells.iter().fold("opened".to_string(), |line, (x, y)| {
+                format!("{} {} {}", line, x, y)
+            }),
+        }
+    }
+
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words.as_slice() {
+            ["hello", ..] => Ok(ToHost::Hello {
+                name: rest(line, "hello"),
+            }),
+            ["opened", cells @ ..] if cells.len() % 2 == 0 => Ok(ToHost::Opened {
+                cells: cells
+                    .chunks(2)
+                    .map(|cell| Ok((number(cell[0])?, number(cell[1])?)))
+                    .collect::<Result<_, String>>()?,
+            }),
+            _ => Err(format!("unexpected message {:?}", line)),
+        }
+    }
+}
+
+impl ToClient {
+    pub fn encode(&self) -> String {
+        match self {
+            ToClient::Welcome { id } => format!("welcome {}", id),
+            ToClient::Joined { id, name } => format!("joined {} {}", id, one_line(name)),
+            ToClient::Left { id } => format!("left {}", id),
+            ToClient::Start(start) => format!(
+                "start {} {} {} {} {} {}",
+                start.width,
+                start.height,
+                start.mines,
+                start.seed,
+                start.first_click.0,
+                start.first_click.1
+            ),
+            ToClient::Progress { id, percent } => format!("progress {} {}", id, percent),
+            ToClient::Finished { id, won, millis } => {
+                format!("finished {} {} {}", id, outcome(*won), millis)
+            }
+            ToClient::Winner { id } => format!("winner {}", id),
+            ToClient::NoWinner => "no-winner".to_string(),
+            ToClient::Error { message } => format!("error {}", one_line(message)),
+        }
+    }
+
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words.as_slice() {
+            ["welcome", id] => Ok(ToClient::Welcome { id: number(id)? }),
+            ["joined", id, ..] => Ok(ToClient::Joined {
+                id: number(id)?,
+                name: rest(&rest(line, "joined"), id),
+            }),
+            ["left", id] => Ok(ToClient::Left { id: number(id)? }),
+            ["start", width, height, mines, seed, x, y] => Ok(ToClient::Start(Start {
+                width: number(width)?,
+                height: number(height)?,
+                mines: number(mines)?,
+                seed: number(seed)?,
+                first_click: (number(x)?, number(y)?),
+            })),
+            ["progress", id, percent] => Ok(ToClient::Progress {
+                id: number(id)?,
+                percent: number(percent)?,
+            }),
+            ["finished", id, won, millis] => Ok(ToClient::Finished {
+                id: number(id)?,
+                won: parse_outcome(won)?,
+                millis: number(millis)?,
+            }),
+            ["winner", id] => Ok(ToClient::Winner { id: number(id)? }),
+            ["no-winner"] => Ok(ToClient::NoWinner),
+            ["error", ..] => Ok(ToClient::Error {
+                message: rest(line, "error"),
+            }),
+            _ => Err(format!("unexpected message {:?}", line)),
+        }
+    }
+}
+
+fn outcome(won: bool) -> &'static str {
+    if won {
+        "won"
+    } else {
+        "lost"
+    }
+}
+
+fn parse_outcome(word: &str) -> Result<bool, String> {
+    match word {
+        "won" => Ok(true),
+        "lost" => Ok(false),
+        _ => Err(format!("expected won or lost, got {:?}", word)),
+    }
+}
@@ -0,0 +1,55 @@
+//replays.mod.rs
+//! Finished games recorded to the `replays` folder in the data directory,
+//! one file per game.
+
+use crate::paths;
+use minesweeper::engine::replay::Replay;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const EXTENSION: &str = "replay";
+
+fn dir() -> io::Result<PathBuf> {
+    paths::data_dir()
+        .map(|dir| dir.join("replays"))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no data directory"))
+}
+
+/// Writes `replay` under a new name and returns where it went.
+pub fn save(replay: &Replay) -> io::Result<PathBuf> {
+    let dir = dir()?;
+    fs::create_dir_all(&dir)?;
+    let finished = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let path = dir.join(format!("{}-{}.{}", finished, replay.seed, EXTENSION));
+    fs::write(&path, replay.encode())?;
+    Ok(path)
+}
+
+pub fn load(path: &Path) -> io::Result<Replay> {
+    let text = fs::read_to_string(path)?;
+    Replay::decode(&text).map_err(|message| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{}: {}", path.display(), message),
+        )
+    })
+}
+
+/// Up to `limit` replay files, newest first.
+pub fn recent(limit: usize) -> Vec<PathBuf> {
+    let Ok(entries) = dir().and_then(fs::read_dir) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == EXTENSION))
+        .collect();
+    // Names start with the finish time, so they sort by age.
+    paths.sort_unstable_by(|a, b| b.cmp(a));
+    paths.truncate(limit);
+    paths
+}
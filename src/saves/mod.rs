@@ -0,0 +1,121 @@
+//saves.mod.rs
+//! Save files in the data directory: one autosave written when the window
+//! closes, a few numbered slots the player saves to by hand and the
+//! endless world.
+
+use crate::paths;
+use minesweeper::engine::{Endless, SavedGame};
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+pub const SLOT_COUNT: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Slot {
+    Auto,
+    Manual(usize),
+}
+
+impl Slot {
+    pub fn all() -> impl Iterator<Item = Slot> {
+        std::iter::once(Slot::Auto).chain((1..=SLOT_COUNT).map(Slot::Manual))
+    }
+
+    pub fn label(self) -> String {
+        match self {
+            Slot::Auto => "Autosave".to_string(),
+            Slot::Manual(n) => format!("Slot {}", n),
+        }
+    }
+
+    fn path(self) -> Option<PathBuf> {
+        let name = match self {
+            Slot::Auto => "autosave.sav".to_string(),
+            Slot::Manual(n) => format!("slot{}.sav", n),
+        };
+        paths::data_dir().map(|dir| dir.join("saves").join(name))
+    }
+}
+
+fn no_data_dir() -> io::Error {
+    io::Error::new(io::ErrorKind::NotFound, "no data directory")
+}
+
+/// `Ok(None)` when the slot is empty.
+pub fn load(slot: Slot) -> io::Result<Option<SavedGame>> {
+    let path = slot.path().ok_or_else(no_data_dir)?;
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    SavedGame::decode(&text).map(Some).map_err(|message| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{}: {}", path.display(), message),
+        )
+    })
+}
+
+pub fn save(slot: Slot, game: &SavedGame) -> io::Result<()> {
+    let path = slot.path().ok_or_else(no_data_dir)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    // Write then rename so a crash mid-save keeps the previous file.
+    let tmp = path.with_extension("sav.tmp");
+    fs::write(&tmp, game.encode())?;
+    fs::rename(tmp, path)
+}
+
+/// Marks the game in `slot` as no longer ranked, e.g. once it has been
+/// loaded or the game it was saved from has ended, so reloading it cannot
+/// retry a ranked game.
+pub fn unrank(slot: Slot) -> io::Result<()> {
+    match load(slot)? {
+        Some(mut game) if game.ranked => {
+            game.ranked = false;
+            save(slot, &game)
+        }
+        _ => Ok(()),
+    }
+}
+
+pub fn clear(slot: Slot) -> io::Result<()> {
+    let path = slot.path().ok_or_else(no_data_dir)?;
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+fn endless_path() -> Option<PathBuf> {
+    paths::data_dir().map(|dir| dir.join("saves").join("endless.sav"))
+}
+
+/// `Ok(None)` when no endless world has been saved yet.
+pub fn load_endless() -> io::Result<Option<Endless>> {
+    let path = endless_path().ok_or_else(no_data_dir)?;
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    Endless::decode(&text).map(Some).map_err(|message| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{}: {}", path.display(), message),
+        )
+    })
+}
+
+pub fn save_endless(game: &Endless) -> io::Result<()> {
+    let path = endless_path().ok_or_else(no_data_dir)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let tmp = path.with_extension("sav.tmp");
+    fs::write(&tmp, game.encode())?;
+    fs::rename(tmp, path)
+}
@@ -0,0 +1,135 @@
+//stats.mod.rs
+//! Summaries of the game log kept by the score store.
+
+use crate::db::GameRecord;
+
+/// Totals over a list of games, oldest first.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Summary {
+    pub played: usize,
+    pub won: usize,
+    /// Wins since the last loss.
+    pub current_streak: usize,
+    pub best_streak: usize,
+    pub average_time: Option<f32>,
+    pub best_time: Option<f32>,
+    pub average_efficiency: Option<f32>,
+    /// Times of the won games, for the histogram.
+    pub win_times: Vec<f32>,
+}
+
+impl Summary {
+    pub fn of<'a>(games: impl IntoIterator<Item = &'a GameRecord>) -> Self {
+        let mut summary = Summary::default();
+        let mut efficiencies = Vec::new();
+        for game in games {
+            summary.played += 1;
+            if game.won {
+                summary.won += 1;
+                summary.current_streak += 1;
+                summary.best_streak = summary.best_streak.max(summary.current_streak);
+                summary.win_times.push(game.time);
+                efficiencies.extend(game.efficiency());
+            } else {
+                summary.current_streak = 0;
+            }
+        }
+        summary.average_time = average(&summary.win_times);
+        summary.best_time = summary.win_times.iter().copied().reduce(f32::min);
+        summary.average_efficiency = average(&efficiencies);
+        summary
+    }
+
+    /// Wins as a percentage of games played.
+    pub fn win_rate(&self) -> Option<f32> {
+        (self.played > 0).then(|| self.won as f32 * 100.0 / self.played as f32)
+    }
+
+    /// Win times split into `buckets` equal ranges between the fastest and
+    /// slowest win. Each entry is the lower bound of the range and how many
+    /// wins fall in it.
+    pub fn histogram(&self, buckets: usize) -> Vec<(f32, usize)> {
+        let (Some(min), Some(max)) = (
+            self.win_times.iter().copied().reduce(f32::min),
+            self.win_times.iter().copied().reduce(f32::max),
+        ) else {
+            return Vec::new();
+        };
+        let buckets = buckets.max(1);
+        let width = ((max - min) / buckets as f32).max(f32::EPSILON);
+        let mut counts = vec![0; buckets];
+        for time in &self.win_times {
+            let i = ((time - min) / width) as usize;
+            counts[i.min(buckets - 1)] += 1;
+        }
+        counts
+            .into_iter()
+            .enumerate()
+            .map(|(i, count)| (min + i as f32 * width, count))
+            .collect()
+    }
+}
+
+fn average(values: &[f32]) -> Option<f32> {
+    (!values.is_empty()).then(|| values.iter().sum::<f32>() / values.len() as f32)
+}
+
+/// Difficulty names in the log, in the order they were first played.
+pub fn difficulties(games: &[GameRecord]) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for game in games {
+        if !names.contains(&game.difficulty) {
+            names.push(game.difficulty.clone());
+        }
+    }
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn game(difficulty: &str, won: bool, time: f32) -> GameRecord {
+        GameRecord {
+            played_at: 1_700_000_000,
+            difficulty: difficulty.to_string(),
+            width: 9,
+            height: 9,
+            mines: 10,
+            won,
+            time,
+            seed: None,
+            bbbv: 20,
+            clicks: if won { 25 } else { 4 },
+            variant: "classic".to_string(),
+            ranked: true,
+        }
+    }
+
+    #[test]
+    fn summary_counts_streaks_and_times() {
+        let games = [
+            game("Easy", true, 30.0),
+            game("Easy", true, 10.0),
+            game("Medium", false, 5.0),
+            game("Easy", true, 20.0),
+        ];
+        let summary = Summary::of(&games);
+        assert_eq!((summary.played, summary.won), (4, 3));
+        assert_eq!((summary.current_streak, summary.best_streak), (1, 2));
+        assert_eq!(summary.best_time, Some(10.0));
+        assert_eq!(summary.average_time, Some(20.0));
+        assert_eq!(summary.average_efficiency, Some(0.8));
+        assert_eq!(summary.win_rate(), Some(75.0));
+        assert_eq!(difficulties(&games), ["Easy", "Medium"]);
+    }
+
+    #[test]
+    fn histogram_spreads_wins_over_the_range() {
+        let games = [10.0, 12.0, 19.0, 30.0].map(|time| game("Easy", true, time));
+        let summary = Summary::of(&games);
+        assert_eq!(summary.histogram(2), vec![(10.0, 3), (20.0, 1)]);
+        assert!(Summary::of(&[]).histogram(4).is_empty());
+        assert_eq!(Summary::default().win_rate(), None);
+    }
+}
@@ -0,0 +1,571 @@
+//tui.mod.rs
+#![deny(clippy::all)]
+//! Terminal frontend, started with `minesweeper tui`. Keys are read raw by
+//! switching the terminal with `stty`; where that is not available the game
+//! falls back to typed commands such as `r 3 4`.
+
+use crate::config::Config;
+use crate::daily;
+use crate::db::{self, ScoreStore};
+use crate::lan;
+use crate::race::RaceClient;
+use minesweeper::engine::{Board, Cell, CellState, Day, GameError, GameStatus, Metrics};
+use std::io::{self, BufRead, Read, Write};
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// One character per count, so wide neighbourhoods and stacked flags keep
+/// the columns lined up.
+const DIGITS: &str = "0123456789abcdefghijklmnopqrstuvwxyz";
+
+const HELP: &str =
+    "arrows/wasd move, space reveal, f flag, c chord, n new game, t daily challenge, q quit";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Key {
+    Up,
+    Down,
+    Left,
+    Right,
+    Enter,
+    Backspace,
+    Char(char),
+}
+
+/// Puts the terminal into raw mode for as long as it lives.
+struct RawMode {
+    saved: String,
+}
+
+impl RawMode {
+    fn enable() -> Option<Self> {
+        let saved = stty(&["-g"])?;
+        stty(&["raw", "-echo"])?;
+        Some(RawMode {
+            saved: saved.trim().to_string(),
+        })
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        stty(&[&self.saved]);
+    }
+}
+
+fn stty(args: &[&str]) -> Option<String> {
+    let output = Command::new("stty")
+        .args(args)
+        .stdin(Stdio::inherit())
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+struct Game {
+    config: Config,
+    scores: Box<dyn ScoreStore>,
+    board: Board,
+    cursor: (usize, usize),
+    started: Option<Instant>,
+    /// Final time, fixed when the game ends.
+    finished_after: Option<Duration>,
+    /// Cleared for repeat attempts at a daily challenge.
+    ranked: bool,
+    message: String,
+    race: Option<RaceClient>,
+}
+
+impl Game {
+    fn new(config: Config) -> Self {
+        let scores = db::open_or_fallback(&config.backend());
+        let mut game = Game {
+            board: Board::with_difficulty(config.difficulty),
+            config,
+            scores,
+            cursor: (0, 0),
+            started: None,
+            finished_after: None,
+            ranked: true,
+            message: String::new(),
+            race: None,
+        };
+        game.restart();
+        game
+    }
+
+    fn restart(&mut self) {
+        self.board = Board::with_difficulty(self.config.difficulty);
+        self.board.set_question_marks(self.config.question_marks);
+        self.cursor = (self.board.width() / 2, self.board.height() / 2);
+        self.started = None;
+        self.finished_after = None;
+        self.ranked = true;
+        self.message = HELP.to_string();
+    }
+
+    /// Today's challenge with its first cell opened. Only the first attempt
+    /// of the day is ranked.
+    fn start_daily(&mut self) {
+        let day = Day::today();
+        self.restart();
+        self.board = day.board();
+        self.board.set_question_marks(self.config.question_marks);
+        let claim = daily::claim(day);
+        self.ranked = *claim.as_ref().unwrap_or(&false);
+        self.cursor = day.first_click();
+        let (x, y) = self.cursor;
+        self.play(|b| b.reveal(x, y));
+        self.message = match claim {
+            Ok(true) => format!("Daily challenge {}.", day),
+            Ok(false) => format!(
+                "Daily challenge {} was already played; this attempt is not ranked.",
+                day
+            ),
+            Err(e) => format!("Failed to record the daily attempt, not ranked: {}", e),
+        };
+    }
+
+    /// Picks up news from the race host, starting the race board when it
+    /// arrives.
+    fn tick(&mut self) {
+        let Some(start) = self.race.as_mut().and_then(RaceClient::poll) else {
+            return;
+        };
+        self.board = start.board();
+        self.board.set_question_marks(self.config.question_marks);
+        self.started = None;
+        self.finished_after = None;
+        self.ranked = false;
+        self.cursor = start.first_click;
+        let (x, y) = self.cursor;
+        self.play(|b| b.reveal(x, y));
+    }
+
+    fn elapsed(&self) -> Duration {
+        match (self.finished_after, self.started) {
+            (Some(time), _) => time,
+            (None, Some(started)) => started.elapsed(),
+            (None, None) => Duration::ZERO,
+        }
+    }
+
+    fn move_cursor(&mut self, dx: isize, dy: isize) {
+        let x = self.cursor.0 as isize + dx;
+        let y = self.cursor.1 as isize + dy;
+        if x >= 0 && y >= 0 && self.board.contains(x as usize, y as usize) {
+            self.cursor = (x as usize, y as usize);
+        }
+    }
+
+    /// Applies a move and reports its outcome in the status line.
+    fn play(&mut self, result: impl FnOnce(&mut Board) -> Result<(), GameError>) {
+        if self.board.is_finished() {
+            self.message = "The game is over; press n for a new one.".to_string();
+            return;
+        }
+        if self.race.as_ref().is_some_and(|race| race.start.is_none()) {
+            self.message = "Waiting for the race to start.".to_string();
+            return;
+        }
+        self.started.get_or_insert_with(Instant::now);
+        self.message = match result(&mut self.board) {
+            Ok(()) => String::new(),
+            Err(e) => e.to_string(),
+        };
+        if self.board.is_finished() {
+            self.finished_after = Some(self.elapsed());
+            let game = db::GameRecord::new(self.elapsed().as_secs_f32(), &self.board, self.ranked);
+            if let Err(e) = self.scores.record_game(&game) {
+                self.message = format!("Failed to record the game: {}", e);
+            }
+        }
+        if let Some(race) = &mut self.race {
+            race.report(&self.board);
+        }
+    }
+
+    fn handle(&mut self, key: Key) -> bool {
+        let (x, y) = self.cursor;
+        match key {
+            Key::Up | Key::Char('w') | Key::Char('k') => self.move_cursor(0, -1),
+            Key::Down | Key::Char('s') | Key::Char('j') => self.move_cursor(0, 1),
+            Key::Left | Key::Char('a') | Key::Char('h') => self.move_cursor(-1, 0),
+            Key::Right | Key::Char('d') | Key::Char('l') => self.move_cursor(1, 0),
+            Key::Char(' ') | Key::Enter | Key::Char('r') => self.play(|b| b.reveal(x, y)),
+            Key::Char('f') => self.play(|b| b.toggle_state(x, y)),
+            Key::Char('c') => self.play(|b| b.chord(x, y)),
+            Key::Char('n') | Key::Char('t') if self.race.is_some() => {
+                self.message = "That is not available during a race.".to_string()
+            }
+            Key::Char('n') => self.restart(),
+            Key::Char('t') => self.start_daily(),
+            Key::Char('q') | Key::Char('\u{3}') => return false,
+            _ => self.message = HELP.to_string(),
+        }
+        true
+    }
+
+    fn qualifies_for_top_10(&self) -> bool {
+        if !self.ranked {
+            return false;
+        }
+        let entry = db::ScoreEntry::new("", self.elapsed().as_secs_f32(), &self.board);
+        db::qualifies(self.scores.as_ref(), &entry)
+    }
+
+    fn submit(&mut self, name: &str) {
+        let entry = db::ScoreEntry::new(name, self.elapsed().as_secs_f32(), &self.board);
+        self.message = match self.scores.add_high_score(&entry) {
+            Ok(()) => format!("Saved to {}.", self.scores.describe()),
+            Err(e) => format!("Failed to save high score: {}", e),
+        };
+    }
+
+    fn high_scores(&self) -> Vec<String> {
+        let difficulty = self.board.difficulty().name();
+        let variant = db::variant(&self.board);
+        match self
+            .scores
+            .get_top_10_scores(difficulty, &variant, db::Ranking::Time)
+        {
+            Ok(scores) => std::iter::once(format!("Top 10 - {} ({})", difficulty, variant))
+                .chain(
+                    scores
+                        .iter()
+                        .enumerate()
+                        .map(|(i, s)| format!("{:>2}. {} - {:.2}s", i + 1, s.name, s.time)),
+                )
+                .collect(),
+            Err(e) => vec![format!("Failed to retrieve high scores: {}", e)],
+        }
+    }
+
+    /// The screen as lines of text, with ANSI colours when `color` is set.
+    fn render(&self, color: bool) -> Vec<String> {
+        let paint = |code: &str, text: String| {
+            if color {
+                format!("\x1b[{}m{}\x1b[0m", code, text)
+            } else {
+                text
+            }
+        };
+        let mut lines = vec![format!(
+            "{}  Flags: {}/{}  Time: {:.1}s  Seed: {}",
+            self.board.difficulty(),
+            self.board.flags_count(),
+            self.board.mine_count(),
+            self.elapsed().as_secs_f32(),
+            self.board.seed()
+        )];
+        lines.push(format!(
+            "    {}",
+            (0..self.board.width())
+                .map(|x| format!("{:<2}", x % 100))
+                .collect::<String>()
+        ));
+        for y in 0..self.board.height() {
+            let mut line = format!("{:>3} ", y);
+            for x in 0..self.board.width() {
+                let (text, code) = match (self.board.cell_state(x, y), self.board.cell(x, y)) {
+                    (CellState::Hidden, _) => ("#", "90"),
+                    (CellState::Flagged, _) => match self.board.flags_at(x, y) as usize {
+                        n @ 2.. => (&DIGITS[n..=n], "1;31"),
+                        _ => ("F", "1;31"),
+                    },
+                    (CellState::Questioned, _) => ("?", "33"),
+                    (CellState::Revealed, Cell::Mine) => ("*", "1;41"),
+                    (CellState::Revealed, Cell::Empty) => (".", "37"),
+                    (CellState::Revealed, Cell::Number(n)) => (
+                        DIGITS.get(*n as usize..=*n as usize).unwrap_or("+"),
+                        ["37", "34", "32", "31", "35", "33", "36", "30", "90"]
+                            .get(*n as usize)
+                            .copied()
+                            .unwrap_or("90"),
+                    ),
+                };
+                let code = if (x, y) == self.cursor && color {
+                    format!("{};7", code)
+                } else {
+                    code.to_string()
+                };
+                let text = if (x, y) == self.cursor && !color {
+                    format!("[{}", text)
+                } else {
+                    format!("{} ", text)
+                };
+                line += &paint(&code, text);
+            }
+            lines.push(line);
+        }
+        lines.push(match self.board.status() {
+            GameStatus::Won => {
+                let metrics = Metrics::of(&self.board, self.elapsed());
+                let value = |v: Option<f32>| v.map_or("-".to_string(), |v| format!("{:.2}", v));
+                paint(
+                    "1;32",
+                    format!(
+                        "You won! 3BV {}  3BV/s {}  IOS {}  RQP {}  Efficiency {}",
+                        metrics.bbbv,
+                        value(metrics.bbbv_per_second()),
+                        value(metrics.ios()),
+                        value(metrics.rqp()),
+                        metrics
+                            .efficiency()
+                            .map_or("-".to_string(), |e| format!("{:.0}%", e * 100.0))
+                    ),
+                )
+            }
+            GameStatus::Lost => paint("1;31", "Game over.".to_string()),
+            GameStatus::Ready | GameStatus::Playing => String::new(),
+        });
+        if let Some(race) = &self.race {
+            lines.push(paint("1", race.status()));
+            lines.extend(
+                race.standings()
+                    .into_iter()
+                    .map(|line| format!("  {}", line)),
+            );
+        }
+        lines.push(self.message.clone());
+        lines
+    }
+}
+
+/// Plays in the terminal until the player quits.
+pub fn run(config: Config) -> io::Result<()> {
+    play(Game::new(config))
+}
+
+/// Joins the race hosted at `address` and plays it in the terminal.
+pub fn race(config: Config, address: &str) -> io::Result<()> {
+    let address = lan::with_port(address, config.race_port);
+    let client = RaceClient::join(&address, &config.player_name)?;
+    let mut game = Game::new(config);
+    game.race = Some(client);
+    game.message = format!("Joined the race at {}.", address);
+    play(game)
+}
+
+fn play(mut game: Game) -> io::Result<()> {
+    match RawMode::enable() {
+        Some(raw) => {
+            let result = run_raw(&mut game);
+            drop(raw);
+            // Leave the last screen visible and the prompt below it.
+            println!();
+            result
+        }
+        None => run_lines(&mut game),
+    }
+}
+
+fn run_raw(game: &mut Game) -> io::Result<()> {
+    let keys = spawn_key_reader();
+    let mut out = io::stdout();
+    write!(out, "\x1b[2J\x1b[?25l")?;
+    let result = (|| loop {
+        game.tick();
+        draw(&mut out, game)?;
+        let key = match keys.recv_timeout(Duration::from_millis(250)) {
+            Ok(key) => key,
+            // Redraw so the timer keeps ticking.
+            Err(RecvTimeoutError::Timeout) => continue,
+            Err(RecvTimeoutError::Disconnected) => return Ok(()),
+        };
+        let was_finished = game.board.is_finished();
+        if !game.handle(key) {
+            return Ok(());
+        }
+        if !was_finished && game.board.status() == GameStatus::Won && game.qualifies_for_top_10() {
+            game.message = "New high score! Type your name and press Enter: ".to_string();
+            draw(&mut out, game)?;
+            if let Some(name) = read_name(&keys, &mut out, game)? {
+                game.submit(&name);
+            }
+        }
+        if !was_finished && game.board.is_finished() && game.race.is_none() {
+            let scores = game.high_scores().join("\r\n");
+            game.message = format!(
+                "{}\r\n{}\r\nPress n for a new game or q to quit.",
+                game.message, scores
+            );
+        }
+    })();
+    write!(out, "\x1b[?25h")?;
+    out.flush()?;
+    result
+}
+
+fn draw(out: &mut impl Write, game: &Game) -> io::Result<()> {
+    write!(out, "\x1b[H")?;
+    for line in game.render(true) {
+        // Raw mode does not turn `\n` into a carriage return.
+        write!(out, "{}\x1b[K\r\n", line)?;
+    }
+    write!(out, "\x1b[J")?;
+    out.flush()
+}
+
+/// Collects a name typed on the status line; `None` if it is left empty.
+fn read_name(
+    keys: &Receiver<Key>,
+    out: &mut impl Write,
+    game: &mut Game,
+) -> io::Result<Option<String>> {
+    let prompt = game.message.clone();
+    let mut name = String::new();
+    loop {
+        match keys.recv() {
+            Ok(Key::Enter) => {
+                let name = name.trim();
+                return Ok((!name.is_empty()).then(|| name.to_string()));
+            }
+            Ok(Key::Backspace) => {
+                name.pop();
+            }
+            Err(_) => return Ok(None),
+            Ok(Key::Char(c)) if !c.is_control() => name.push(c),
+            Ok(_) => {}
+        }
+        game.message = format!("{}{}", prompt, name);
+        draw(out, game)?;
+    }
+}
+
+/// Reads stdin on a thread so the main loop can redraw while waiting.
+fn spawn_key_reader() -> Receiver<Key> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let mut bytes = io::stdin().lock().bytes().map_while(Result::ok);
+        while let Some(byte) = bytes.next() {
+            let key = match byte {
+                b'\r' | b'\n' => Key::Enter,
+                0x7f | 0x08 => Key::Backspace,
+                // Arrow keys arrive as ESC [ A..D.
+                0x1b => match (bytes.next(), bytes.next()) {
+                    (Some(b'['), Some(b'A')) => Key::Up,
+                    (Some(b'['), Some(b'B')) => Key::Down,
+                    (Some(b'['), Some(b'C')) => Key::Right,
+                    (Some(b'['), Some(b'D')) => Key::Left,
+                    _ => Key::Char('\u{1b}'),
+                },
+                byte => Key::Char(byte as char),
+            };
+            if sender.send(key).is_err() {
+                return;
+            }
+        }
+    });
+    receiver
+}
+
+/// Fallback for terminals `stty` cannot drive: one typed command per move.
+fn run_lines(game: &mut Game) -> io::Result<()> {
+    println!(
+        "Commands: r X Y reveal, f X Y flag, c X Y chord, n new game, t daily challenge, q quit"
+    );
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    loop {
+        game.tick();
+        for line in game.render(false) {
+            println!("{}", line);
+        }
+        print!("> ");
+        io::stdout().flush()?;
+        let Some(line) = lines.next().transpose()? else {
+            return Ok(());
+        };
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let coords = match (words.get(1), words.get(2)) {
+            (Some(x), Some(y)) => x.parse().ok().zip(y.parse().ok()),
+            _ => None,
+        };
+        if let Some((x, y)) = coords {
+            game.cursor = (x, y);
+        }
+        let was_finished = game.board.is_finished();
+        let key = match (words.first().copied(), coords) {
+            (Some("r"), Some(_)) => Key::Char('r'),
+            (Some("f"), Some(_)) => Key::Char('f'),
+            (Some("c"), Some(_)) => Key::Char('c'),
+            (Some("n"), _) => Key::Char('n'),
+            (Some("t"), _) => Key::Char('t'),
+            (Some("q"), _) => Key::Char('q'),
+            // An empty line just refreshes, e.g. while waiting for a race.
+            (None, _) => continue,
+            _ => {
+                game.message = "Commands: r X Y, f X Y, c X Y, n, t, q".to_string();
+                continue;
+            }
+        };
+        if !game.board.contains(game.cursor.0, game.cursor.1) {
+            game.cursor = (0, 0);
+            game.message = "That cell is off the board.".to_string();
+            continue;
+        }
+        if !game.handle(key) {
+            return Ok(());
+        }
+        if !was_finished && game.board.status() == GameStatus::Won && game.qualifies_for_top_10() {
+            print!("New high score! Your name: ");
+            io::stdout().flush()?;
+            if let Some(name) = lines.next().transpose()?.filter(|n| !n.trim().is_empty()) {
+                game.submit(name.trim());
+            }
+        }
+        if !was_finished && game.board.is_finished() && game.race.is_none() {
+            for line in game.high_scores() {
+                println!("{}", line);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::StoreKind;
+
+    fn game() -> Game {
+        Game::new(Config {
+            store: Some(StoreKind::Memory),
+            ..Config::default()
+        })
+    }
+
+    #[test]
+    fn keys_move_the_cursor_and_play() {
+        let mut game = game();
+        game.cursor = (0, 0);
+        game.handle(Key::Up);
+        game.handle(Key::Char('h'));
+        assert_eq!(game.cursor, (0, 0));
+        game.handle(Key::Right);
+        game.handle(Key::Char('j'));
+        assert_eq!(game.cursor, (1, 1));
+
+        assert!(game.handle(Key::Char(' ')));
+        assert!(game.board.is_revealed(1, 1));
+        assert!(game.started.is_some());
+        game.handle(Key::Char('n'));
+        assert!(!game.board.is_initialized());
+        assert!(!game.handle(Key::Char('q')));
+    }
+
+    #[test]
+    fn render_marks_the_cursor_without_colour() {
+        let game = game();
+        let lines = game.render(false);
+        let (x, y) = game.cursor;
+        assert_eq!(lines.len(), game.board.height() + 4);
+        assert_eq!(&lines[2 + y][4 + 2 * x..6 + 2 * x], "[#");
+        assert!(!lines.concat().contains('\x1b'));
+    }
+}